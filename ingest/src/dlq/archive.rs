@@ -0,0 +1,301 @@
+//! Archival tier for batches TTL cleanup or quota enforcement would otherwise destroy.
+//!
+//! A dead-letter queue exists to preserve records ClickHouse couldn't ingest, so an
+//! expired or over-quota batch hitting `storage::delete_batch` for good defeats that
+//! purpose. When `DlqConfig::archive_mode` is `Archive`, `scheduler::QuotaEnforcementHandler`
+//! and `scheduler::TtlCleanupHandler` upload a batch here instead of deleting it outright,
+//! under a `dlq/<table>/<date>/` prefix in `DlqConfig::archive_bucket` -- day-partitioned so
+//! a bucket listing stays browsable after months of retention. `replay_archived_batches`
+//! is the matching restore path: it enumerates a table's archived objects and re-attempts
+//! insertion the same way `replay::replay_one_batch` does for batches still on local disk.
+//!
+//! `archive_batch` uploads before it deletes anything local, so a failed upload can never
+//! drop a batch that would otherwise have survived -- the caller sees the error and the
+//! batch is left exactly where it was, to be retried on the next TTL/quota pass.
+
+use crate::dlq::object_store::ObjectStore;
+use crate::dlq::storage::{self, DlqError};
+use crate::dlq::types::{BatchEnvelope, ReplayStats};
+use crate::traits::{DatabaseRow, EnrichmentData, InputData};
+use chrono::{DateTime, Utc};
+use clickhouse::Client;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Whether TTL cleanup and quota enforcement destroy an evicted batch or archive it to
+/// object storage first. Selected via `DlqConfig::archive_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveMode {
+    /// Evicted batches are deleted outright. The default, and the only option that
+    /// doesn't require an archive bucket to be configured.
+    #[default]
+    HardDelete,
+    /// Evicted batches are uploaded to `DlqConfig::archive_bucket` before being removed
+    /// from local disk.
+    Archive,
+}
+
+impl std::str::FromStr for ArchiveMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "delete" | "hard_delete" => Ok(Self::HardDelete),
+            "archive" => Ok(Self::Archive),
+            other => Err(format!("unknown DLQ archive mode: {other}")),
+        }
+    }
+}
+
+/// Lifetime count of batches archived (as opposed to hard-deleted) by TTL cleanup or
+/// quota enforcement. Process-local; resets on restart, same as
+/// `storage::batches_quarantined_total`.
+static BATCHES_ARCHIVED_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Current value of the lifetime archive counter, for `DlqHealthStats` reporting.
+pub fn batches_archived_total() -> u64 {
+    BATCHES_ARCHIVED_TOTAL.load(Ordering::Relaxed)
+}
+
+/// Object key a batch is archived under, partitioned by the day it was originally
+/// persisted rather than the day it happens to be evicted.
+fn archive_key(table_name: &str, created_at: DateTime<Utc>, filename: &str) -> String {
+    format!(
+        "dlq/{table_name}/{}/{filename}",
+        created_at.format("%Y-%m-%d")
+    )
+}
+
+/// Archives the batch at `batch_path` to `bucket` and removes the local copy (and its
+/// manifest), returning the object key it was archived under.
+pub async fn archive_batch(
+    object_store: &Arc<dyn ObjectStore>,
+    bucket: &str,
+    batch_path: &Path,
+) -> Result<String, DlqError> {
+    let meta = storage::peek_batch_meta(batch_path).await?;
+    let filename = batch_path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| {
+            DlqError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Batch path has no filename",
+            ))
+        })?
+        .to_string();
+    let key = archive_key(&meta.table_name, meta.timestamp, &filename);
+
+    let bytes = tokio::fs::read(batch_path).await?;
+    object_store.put(bucket, &key, bytes).await?;
+
+    // Only remove the local copy once the upload has actually landed.
+    storage::delete_batch(batch_path).await?;
+
+    BATCHES_ARCHIVED_TOTAL.fetch_add(1, Ordering::Relaxed);
+    info!(
+        path = %batch_path.display(),
+        bucket = %bucket,
+        key = %key,
+        "Archived DLQ batch to object storage instead of deleting it"
+    );
+
+    Ok(key)
+}
+
+/// Lists a table's archived object keys. Lexical order matches chronological order here,
+/// since keys are partitioned by a `YYYY-MM-DD` prefix.
+pub async fn list_archived_batches(
+    object_store: &Arc<dyn ObjectStore>,
+    bucket: &str,
+    table_name: &str,
+) -> Result<Vec<String>, DlqError> {
+    let prefix = format!("dlq/{table_name}/");
+    let mut keys = object_store.list(bucket, &prefix).await?;
+    keys.sort();
+    Ok(keys)
+}
+
+/// Replays every archived batch for `table_name` back into ClickHouse, deleting each
+/// object on success. A batch that fails to replay is left archived rather than
+/// quarantined -- it already represents data evicted off local disk entirely, so there's
+/// no local sidecar left to bump a retry count on, and the next replay pass will simply
+/// try it again.
+pub async fn replay_archived_batches<F, R, E>(
+    client: &Client,
+    object_store: &Arc<dyn ObjectStore>,
+    bucket: &str,
+    table_name: &str,
+) -> Result<ReplayStats, DlqError>
+where
+    F: DatabaseRow<R, E> + Send + 'static + Clone,
+    R: InputData,
+    E: EnrichmentData,
+{
+    let mut stats = ReplayStats::default();
+
+    for key in list_archived_batches(object_store, bucket, table_name).await? {
+        let bytes = match object_store.get(bucket, &key).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!(bucket = %bucket, %key, error = %e, "Failed to fetch archived batch");
+                stats.failed_batches += 1;
+                continue;
+            }
+        };
+
+        let json_data = match zstd::stream::decode_all(bytes.as_slice()) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(bucket = %bucket, %key, error = %e, "Failed to decompress archived batch");
+                stats.failed_batches += 1;
+                continue;
+            }
+        };
+
+        let envelope: BatchEnvelope<F> = match serde_json::from_slice(&json_data) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                warn!(bucket = %bucket, %key, error = %e, "Failed to parse archived batch");
+                stats.failed_batches += 1;
+                continue;
+            }
+        };
+
+        match crate::dlq::replay::insert_batch_with_retries(
+            client,
+            &envelope.records,
+            table_name,
+            3,
+        )
+        .await
+        {
+            Ok(_) => {
+                if let Err(e) = object_store.delete(bucket, &key).await {
+                    warn!(
+                        bucket = %bucket,
+                        %key,
+                        error = %e,
+                        "Replayed archived batch but failed to remove it from the archive"
+                    );
+                }
+                stats.replayed += envelope.record_count;
+                info!(bucket = %bucket, %key, records = envelope.record_count, "Replayed archived DLQ batch");
+            }
+            Err(e) => {
+                warn!(bucket = %bucket, %key, error = %e, "Failed to replay archived batch, leaving it archived for the next pass");
+                stats.failed_records += envelope.record_count;
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlq::object_store::InMemoryObjectStore;
+    use crate::dlq::{init_directories, DlqConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_mode_from_str() {
+        assert_eq!("delete".parse::<ArchiveMode>().unwrap(), ArchiveMode::HardDelete);
+        assert_eq!("hard_delete".parse::<ArchiveMode>().unwrap(), ArchiveMode::HardDelete);
+        assert_eq!("archive".parse::<ArchiveMode>().unwrap(), ArchiveMode::Archive);
+        assert_eq!("ARCHIVE".parse::<ArchiveMode>().unwrap(), ArchiveMode::Archive);
+        assert!("bogus".parse::<ArchiveMode>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_archive_batch_uploads_and_removes_local_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        init_directories(&config).await.unwrap();
+
+        let backend = crate::dlq::backend::build_backend(&config, None);
+        crate::dlq::persist_batch(&vec![1, 2, 3], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+        let batches = storage::list_batches(&config.base_path, "mlop_metrics")
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch_path = &batches[0];
+
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemoryObjectStore::new());
+        let before = batches_archived_total();
+        let key = archive_batch(&store, "dlq-archive", batch_path).await.unwrap();
+
+        assert!(key.starts_with("dlq/mlop_metrics/"));
+        assert!(!batch_path.exists());
+        assert_eq!(batches_archived_total(), before + 1);
+
+        let archived = list_archived_batches(&store, "dlq-archive", "mlop_metrics")
+            .await
+            .unwrap();
+        assert_eq!(archived, vec![key]);
+    }
+
+    #[tokio::test]
+    async fn test_archive_batch_leaves_local_copy_on_upload_failure() {
+        struct FailingObjectStore;
+
+        #[async_trait::async_trait]
+        impl ObjectStore for FailingObjectStore {
+            async fn put(
+                &self,
+                _bucket: &str,
+                _key: &str,
+                _bytes: Vec<u8>,
+            ) -> Result<(), crate::dlq::object_store::ObjectStoreError> {
+                Err(crate::dlq::object_store::ObjectStoreError::Request(
+                    "simulated upload failure".to_string(),
+                ))
+            }
+            async fn get(
+                &self,
+                _bucket: &str,
+                _key: &str,
+            ) -> Result<Vec<u8>, crate::dlq::object_store::ObjectStoreError> {
+                unreachable!("not exercised by this test")
+            }
+            async fn delete(
+                &self,
+                _bucket: &str,
+                _key: &str,
+            ) -> Result<(), crate::dlq::object_store::ObjectStoreError> {
+                unreachable!("not exercised by this test")
+            }
+            async fn list(
+                &self,
+                _bucket: &str,
+                _prefix: &str,
+            ) -> Result<Vec<String>, crate::dlq::object_store::ObjectStoreError> {
+                unreachable!("not exercised by this test")
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        init_directories(&config).await.unwrap();
+
+        let backend = crate::dlq::backend::build_backend(&config, None);
+        crate::dlq::persist_batch(&vec![1], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+        let batches = storage::list_batches(&config.base_path, "mlop_metrics")
+            .await
+            .unwrap();
+        let batch_path = &batches[0];
+
+        let store: Arc<dyn ObjectStore> = Arc::new(FailingObjectStore);
+        let result = archive_batch(&store, "dlq-archive", batch_path).await;
+
+        assert!(result.is_err());
+        assert!(batch_path.exists());
+    }
+}
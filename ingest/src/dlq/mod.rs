@@ -6,16 +6,27 @@
 //! - Clean up old batches based on TTL and disk quota
 //!
 //! The DLQ ensures zero data loss by preventing record drops when ClickHouse is unavailable.
+//!
+//! Replay and cleanup run as a single prioritized pass per table rather than as
+//! independent timers — see `scheduler` for the `BatchHandler` trait and the loop that
+//! drives it.
 
-pub mod cleanup;
+pub mod archive;
+pub mod backend;
+pub mod object_store;
 pub mod replay;
+pub mod rocksdb_backend;
+pub mod scheduler;
 pub mod storage;
 pub mod types;
 
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::fs;
 use tracing::info;
 
+use object_store::ObjectStore;
+
 /// All ClickHouse table names that support DLQ
 pub const DLQ_TABLE_NAMES: &[&str] = &[
     crate::config::METRICS_TABLE_NAME,
@@ -37,10 +48,48 @@ pub struct DlqConfig {
     pub batch_ttl_hours: u64,
     /// Whether to replay batches on startup
     pub replay_on_startup: bool,
-    /// Replay interval in seconds (for background replay)
+    /// Tick interval in seconds for each table's `scheduler::DlqScheduler` loop, which
+    /// drives replay, quota enforcement, and TTL cleanup as a single prioritized pass.
     pub replay_interval_secs: u64,
-    /// Cleanup interval in seconds (for expired batch cleanup and quota enforcement)
-    pub cleanup_interval_secs: u64,
+    /// Maximum number of batches replayed concurrently per table
+    pub max_concurrent_replays: usize,
+    /// Maximum number of failed replay attempts before a batch is quarantined
+    pub max_replay_attempts: u32,
+    /// Soft byte budget for on-disk DLQ batches. When persisting a new batch would push
+    /// total usage over this budget, the oldest batches are evicted to make room rather
+    /// than failing the persist (see `storage::evict_oldest_until_within_budget`).
+    /// `None` disables budget-based eviction (only `max_disk_mb`'s hard quota applies).
+    pub max_disk_bytes: Option<u64>,
+    /// Fraction of the `base_path` mount's total capacity that must remain free after a
+    /// persist. Enforced independently of `max_disk_mb`, so the DLQ stays a well-behaved
+    /// tenant even on a volume shared with ClickHouse data or logs. `0.0` disables this
+    /// check (see `storage::check_disk_quota`).
+    pub reserved_disk_ratio: f64,
+    /// Object-storage bucket new batches spill to once local usage crosses
+    /// `remote_spill_high_water_ratio`. `None` disables the remote tier entirely,
+    /// regardless of whether an `ObjectStore` is passed to `persist_batch`.
+    pub remote_spill_bucket: Option<String>,
+    /// Fraction of `max_disk_mb` at which `persist_batch` starts spilling new batches to
+    /// `remote_spill_bucket` instead of local disk.
+    pub remote_spill_high_water_ratio: f64,
+    /// Opt-in: write persisted batches with O_DIRECT instead of through the page cache,
+    /// so a sustained DLQ write storm during a ClickHouse outage doesn't evict hot pages
+    /// the rest of the process needs. Falls back to a buffered write automatically if the
+    /// filesystem rejects O_DIRECT (see `storage::write_batch_contents`).
+    pub direct_io: bool,
+    /// Which `backend::DlqBackend` the scheduler's quota-enforcement and TTL-cleanup
+    /// handlers use for bookkeeping (list/delete/usage). Lets an operator move the DLQ's
+    /// pending-batch accounting off local disk entirely on ephemeral/container
+    /// filesystems; see `backend::build_backend`.
+    pub backend: backend::DlqBackendKind,
+    /// Whether TTL cleanup and quota enforcement archive an evicted batch to
+    /// `archive_bucket` instead of destroying it. `HardDelete` keeps today's semantics
+    /// for deployments that haven't configured an archive bucket; see `archive`.
+    pub archive_mode: archive::ArchiveMode,
+    /// Object-storage bucket evicted batches are archived to when `archive_mode` is
+    /// `Archive`. Falls back to hard-deleting with a warning if `archive_mode` is
+    /// `Archive` but this is unset, or no `ObjectStore` is configured.
+    pub archive_bucket: Option<String>,
 }
 
 impl DlqConfig {
@@ -70,10 +119,39 @@ impl DlqConfig {
                 .unwrap_or_else(|_| "60".to_string())
                 .parse()
                 .unwrap_or(60),
-            cleanup_interval_secs: std::env::var("DLQ_CLEANUP_INTERVAL_SECS")
-                .unwrap_or_else(|_| "3600".to_string())
+            max_concurrent_replays: std::env::var("DLQ_MAX_CONCURRENT_REPLAYS")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse()
+                .unwrap_or(4),
+            max_replay_attempts: std::env::var("DLQ_MAX_REPLAY_ATTEMPTS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
+            max_disk_bytes: std::env::var("DLQ_MAX_DISK_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            reserved_disk_ratio: std::env::var("DLQ_RESERVED_DISK_RATIO")
+                .unwrap_or_else(|_| "0.1".to_string())
                 .parse()
-                .unwrap_or(3600),
+                .unwrap_or(0.1),
+            remote_spill_bucket: std::env::var("DLQ_REMOTE_SPILL_BUCKET").ok(),
+            remote_spill_high_water_ratio: std::env::var("DLQ_REMOTE_SPILL_HIGH_WATER_RATIO")
+                .unwrap_or_else(|_| "0.8".to_string())
+                .parse()
+                .unwrap_or(0.8),
+            direct_io: std::env::var("DLQ_DIRECT_IO")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            backend: std::env::var("DLQ_BACKEND")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            archive_mode: std::env::var("DLQ_ARCHIVE_MODE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            archive_bucket: std::env::var("DLQ_ARCHIVE_BUCKET").ok(),
         }
     }
 
@@ -86,7 +164,16 @@ impl DlqConfig {
             batch_ttl_hours: 24,
             replay_on_startup: false,
             replay_interval_secs: 10,
-            cleanup_interval_secs: 60,
+            max_concurrent_replays: 4,
+            max_replay_attempts: 10,
+            max_disk_bytes: None,
+            reserved_disk_ratio: 0.0,
+            remote_spill_bucket: None,
+            remote_spill_high_water_ratio: 0.8,
+            direct_io: false,
+            backend: backend::DlqBackendKind::Filesystem,
+            archive_mode: archive::ArchiveMode::HardDelete,
+            archive_bucket: None,
         }
     }
 }
@@ -111,6 +198,19 @@ pub async fn init_directories(config: &DlqConfig) -> Result<(), std::io::Error>
     let metadata_dir = config.base_path.join(".metadata");
     fs::create_dir_all(&metadata_dir).await?;
 
+    // Reap any `*.tmp` files left behind by a crash mid-write in a previous run; their
+    // writer is gone, so they can never be completed and would otherwise sit on disk
+    // forever.
+    let reap_stats = storage::reap_orphaned_temp_files(&config.base_path)
+        .await
+        .map_err(std::io::Error::other)?;
+    if reap_stats.deleted > 0 {
+        info!(
+            deleted = reap_stats.deleted,
+            "Reaped orphaned DLQ temp files left by a previous crash"
+        );
+    }
+
     info!(
         path = %config.base_path.display(),
         "DLQ directories initialized"
@@ -121,11 +221,24 @@ pub async fn init_directories(config: &DlqConfig) -> Result<(), std::io::Error>
 
 /// Persists a batch to the DLQ
 ///
-/// This is the main entry point for persisting failed batches.
+/// This is the main entry point for persisting failed batches. If `object_store` is
+/// given and `config.remote_spill_bucket` is configured, a batch persisted while local
+/// usage is already above `remote_spill_high_water_ratio` of `max_disk_mb`
+/// transparently spills to that bucket instead of local disk (see
+/// `storage::persist_batch_remote`), trading network latency for headroom rather than
+/// rejecting the write. A remote upload failure falls back to the normal local path so
+/// an object-store outage can't turn into a dropped batch.
+///
+/// Short of that remote-spill case, the batch is written through `backend` (selected by
+/// `DlqConfig::backend`, see `backend::build_backend`) rather than straight to local
+/// disk, so a table configured for the object-store or RocksDB backend actually lands
+/// there instead of silently writing local files the configured backend never looks at.
 pub async fn persist_batch<T>(
     records: &[T],
     table_name: String,
     config: &DlqConfig,
+    backend: &Arc<dyn backend::DlqBackend>,
+    object_store: Option<&Arc<dyn ObjectStore>>,
 ) -> Result<(), storage::DlqError>
 where
     T: serde::Serialize + Clone,
@@ -134,11 +247,64 @@ where
         return Err(storage::DlqError::Disabled);
     }
 
-    // Check disk quota before persisting
-    storage::check_disk_quota(&config.base_path, config.max_disk_mb, records.len()).await?;
+    if let (Some(store), Some(bucket)) = (object_store, &config.remote_spill_bucket) {
+        let current_bytes = storage::calculate_disk_usage(&config.base_path).await?;
+        let high_water_bytes = (config.max_disk_mb * 1024 * 1024) as f64
+            * config.remote_spill_high_water_ratio;
+
+        if current_bytes as f64 > high_water_bytes {
+            match storage::persist_batch_remote(
+                records,
+                table_name.clone(),
+                &config.base_path,
+                bucket,
+                store.as_ref(),
+            )
+            .await
+            {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    tracing::warn!(
+                        error = %e,
+                        "Remote DLQ spill failed, falling back to local disk"
+                    );
+                }
+            }
+        }
+    }
 
-    // Persist the batch
-    storage::persist_batch(records, table_name, &config.base_path).await?;
+    // Check disk quota before persisting. This is always measured against local disk
+    // usage under `base_path`, since that's what `DlqConfig::max_disk_mb` budgets even
+    // when the configured backend isn't the filesystem one (the RocksDB backend also
+    // lives under `base_path`; an object-store backend's usage isn't local disk at all,
+    // so this check is a conservative no-op for it rather than a meaningful limit).
+    storage::check_disk_quota(
+        &config.base_path,
+        config.max_disk_mb,
+        config.reserved_disk_ratio,
+        records.len(),
+    )
+    .await?;
+
+    // Persist the batch through the configured backend.
+    let compressed = storage::build_compressed_envelope(records, table_name.clone())?;
+    backend.write_batch(&table_name, compressed).await?;
+
+    // Best-effort soft eviction: if a byte budget is configured and persisting this
+    // batch pushed usage over it, drop the oldest batches rather than failing ingestion
+    // or letting the DLQ fill the volume. A failure here is logged but not propagated,
+    // since the batch we just persisted is already safely on disk.
+    if let Some(max_disk_bytes) = config.max_disk_bytes {
+        if let Err(e) = storage::evict_oldest_until_within_budget(
+            &config.base_path,
+            max_disk_bytes,
+            Some(config.max_replay_attempts),
+        )
+        .await
+        {
+            tracing::warn!(error = %e, "Failed to evict DLQ batches under disk budget");
+        }
+    }
 
     Ok(())
 }
@@ -163,6 +329,16 @@ mod tests {
         std::env::set_var("DLQ_TTL_HOURS", "72");
         std::env::set_var("DLQ_REPLAY_ON_STARTUP", "false");
         std::env::set_var("DLQ_REPLAY_INTERVAL_SECS", "30");
+        std::env::set_var("DLQ_MAX_CONCURRENT_REPLAYS", "8");
+        std::env::set_var("DLQ_MAX_REPLAY_ATTEMPTS", "20");
+        std::env::set_var("DLQ_MAX_DISK_BYTES", "1048576");
+        std::env::set_var("DLQ_RESERVED_DISK_RATIO", "0.2");
+        std::env::set_var("DLQ_REMOTE_SPILL_BUCKET", "dlq-overflow");
+        std::env::set_var("DLQ_REMOTE_SPILL_HIGH_WATER_RATIO", "0.5");
+        std::env::set_var("DLQ_DIRECT_IO", "true");
+        std::env::set_var("DLQ_BACKEND", "object_store");
+        std::env::set_var("DLQ_ARCHIVE_MODE", "archive");
+        std::env::set_var("DLQ_ARCHIVE_BUCKET", "dlq-archive");
 
         let config = DlqConfig::from_env();
 
@@ -173,6 +349,16 @@ mod tests {
         std::env::remove_var("DLQ_TTL_HOURS");
         std::env::remove_var("DLQ_REPLAY_ON_STARTUP");
         std::env::remove_var("DLQ_REPLAY_INTERVAL_SECS");
+        std::env::remove_var("DLQ_MAX_CONCURRENT_REPLAYS");
+        std::env::remove_var("DLQ_MAX_REPLAY_ATTEMPTS");
+        std::env::remove_var("DLQ_MAX_DISK_BYTES");
+        std::env::remove_var("DLQ_RESERVED_DISK_RATIO");
+        std::env::remove_var("DLQ_REMOTE_SPILL_BUCKET");
+        std::env::remove_var("DLQ_REMOTE_SPILL_HIGH_WATER_RATIO");
+        std::env::remove_var("DLQ_DIRECT_IO");
+        std::env::remove_var("DLQ_BACKEND");
+        std::env::remove_var("DLQ_ARCHIVE_MODE");
+        std::env::remove_var("DLQ_ARCHIVE_BUCKET");
 
         assert!(config.enabled);
         assert_eq!(config.base_path, PathBuf::from("/tmp/test-dlq"));
@@ -180,6 +366,16 @@ mod tests {
         assert_eq!(config.batch_ttl_hours, 72);
         assert!(!config.replay_on_startup);
         assert_eq!(config.replay_interval_secs, 30);
+        assert_eq!(config.max_concurrent_replays, 8);
+        assert_eq!(config.max_replay_attempts, 20);
+        assert_eq!(config.max_disk_bytes, Some(1048576));
+        assert_eq!(config.reserved_disk_ratio, 0.2);
+        assert_eq!(config.remote_spill_bucket, Some("dlq-overflow".to_string()));
+        assert_eq!(config.remote_spill_high_water_ratio, 0.5);
+        assert!(config.direct_io);
+        assert_eq!(config.backend, backend::DlqBackendKind::ObjectStore);
+        assert_eq!(config.archive_mode, archive::ArchiveMode::Archive);
+        assert_eq!(config.archive_bucket, Some("dlq-archive".to_string()));
     }
 
     #[test]
@@ -193,6 +389,15 @@ mod tests {
         std::env::remove_var("DLQ_TTL_HOURS");
         std::env::remove_var("DLQ_REPLAY_ON_STARTUP");
         std::env::remove_var("DLQ_REPLAY_INTERVAL_SECS");
+        std::env::remove_var("DLQ_MAX_CONCURRENT_REPLAYS");
+        std::env::remove_var("DLQ_MAX_REPLAY_ATTEMPTS");
+        std::env::remove_var("DLQ_MAX_DISK_BYTES");
+        std::env::remove_var("DLQ_RESERVED_DISK_RATIO");
+        std::env::remove_var("DLQ_REMOTE_SPILL_BUCKET");
+        std::env::remove_var("DLQ_REMOTE_SPILL_HIGH_WATER_RATIO");
+        std::env::remove_var("DLQ_DIRECT_IO");
+        std::env::remove_var("DLQ_ARCHIVE_MODE");
+        std::env::remove_var("DLQ_ARCHIVE_BUCKET");
 
         let config = DlqConfig::from_env();
 
@@ -202,6 +407,15 @@ mod tests {
         assert_eq!(config.batch_ttl_hours, 168);
         assert!(config.replay_on_startup);
         assert_eq!(config.replay_interval_secs, 60);
+        assert_eq!(config.max_concurrent_replays, 4);
+        assert_eq!(config.max_replay_attempts, 10);
+        assert_eq!(config.max_disk_bytes, None);
+        assert_eq!(config.reserved_disk_ratio, 0.1);
+        assert_eq!(config.remote_spill_bucket, None);
+        assert_eq!(config.remote_spill_high_water_ratio, 0.8);
+        assert!(!config.direct_io);
+        assert_eq!(config.archive_mode, archive::ArchiveMode::HardDelete);
+        assert_eq!(config.archive_bucket, None);
     }
 
     #[tokio::test]
@@ -219,6 +433,23 @@ mod tests {
         assert!(temp_dir.path().join(".metadata").exists());
     }
 
+    #[tokio::test]
+    async fn test_init_directories_reaps_orphaned_temp_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+
+        // Simulate a crash mid-write on a previous run: the table directory already
+        // exists with a dangling `.tmp` file in it.
+        let table_dir = temp_dir.path().join("mlop_metrics");
+        fs::create_dir_all(&table_dir).await.unwrap();
+        let orphaned = table_dir.join("2024-01-01T00-00-00.000_crashed.json.zst.tmp");
+        fs::write(&orphaned, b"partial").await.unwrap();
+
+        init_directories(&config).await.unwrap();
+
+        assert!(!orphaned.exists());
+    }
+
     #[tokio::test]
     async fn test_persist_batch_integration() {
         let temp_dir = TempDir::new().unwrap();
@@ -227,7 +458,8 @@ mod tests {
         init_directories(&config).await.unwrap();
 
         let records = vec![1, 2, 3, 4, 5];
-        let result = persist_batch(&records, "mlop_metrics".to_string(), &config).await;
+        let backend = backend::build_backend(&config, None);
+        let result = persist_batch(&records, "mlop_metrics".to_string(), &config, &backend, None).await;
 
         assert!(result.is_ok());
 
@@ -245,8 +477,66 @@ mod tests {
         config.enabled = false;
 
         let records = vec![1, 2, 3];
-        let result = persist_batch(&records, "mlop_metrics".to_string(), &config).await;
+        let backend = backend::build_backend(&config, None);
+        let result = persist_batch(&records, "mlop_metrics".to_string(), &config, &backend, None).await;
 
         assert!(matches!(result, Err(storage::DlqError::Disabled)));
     }
+
+    #[tokio::test]
+    async fn test_persist_batch_spills_remote_once_over_high_water_mark() {
+        use crate::dlq::object_store::InMemoryObjectStore;
+        use std::sync::Arc as StdArc;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        // A ratio of 0.0 means any existing usage at all is "over" the high-water mark.
+        config.remote_spill_bucket = Some("dlq-overflow".to_string());
+        config.remote_spill_high_water_ratio = 0.0;
+
+        init_directories(&config).await.unwrap();
+
+        let store: StdArc<dyn ObjectStore> = StdArc::new(InMemoryObjectStore::new());
+        let backend = backend::build_backend(&config, None);
+
+        // First batch: local usage starts at zero, so it's still under the (zero) high
+        // water mark and lands on local disk.
+        persist_batch(&vec![1], "mlop_metrics".to_string(), &config, &backend, Some(&store))
+            .await
+            .unwrap();
+
+        // Second batch: local usage is now non-zero, so this one spills remote.
+        persist_batch(&vec![2], "mlop_metrics".to_string(), &config, &backend, Some(&store))
+            .await
+            .unwrap();
+
+        let (local, remote) =
+            storage::count_local_and_remote_batches(&config.base_path, "mlop_metrics")
+                .await
+                .unwrap();
+        assert_eq!(local, 1);
+        assert_eq!(remote, 1);
+    }
+
+    #[tokio::test]
+    async fn test_persist_batch_falls_back_to_local_when_remote_spill_bucket_unset() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+
+        init_directories(&config).await.unwrap();
+
+        // No object store and no bucket configured: behaves exactly like the purely
+        // local path, even though `object_store` isn't passed.
+        let backend = backend::build_backend(&config, None);
+        persist_batch(&vec![1], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+
+        let (local, remote) =
+            storage::count_local_and_remote_batches(&config.base_path, "mlop_metrics")
+                .await
+                .unwrap();
+        assert_eq!(local, 1);
+        assert_eq!(remote, 0);
+    }
 }
@@ -1,256 +1,413 @@
+use crate::dlq::object_store::ObjectStore;
 use crate::dlq::storage::{self, DlqError};
 use crate::dlq::types::{BatchEnvelope, ReplayStats};
 use crate::dlq::DlqConfig;
 use crate::traits::{DatabaseRow, EnrichmentData, InputData};
 use clickhouse::Client;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::time::{interval, Duration};
+use tokio::task::JoinSet;
+use tokio::time::Duration;
 use tracing::{error, info, warn};
 
-/// Replays all persisted batches for a given table on startup
+/// Outcome of replaying a single persisted batch, produced inside a `JoinSet` task.
+pub(crate) struct BatchReplayOutcome {
+    pub(crate) batch_path: PathBuf,
+    pub(crate) result: BatchReplayResult,
+}
+
+pub(crate) enum BatchReplayResult {
+    /// Batch failed to load from disk
+    LoadFailed,
+    /// Batch was inserted and its file deleted
+    Replayed { record_count: usize },
+    /// Batch failed to insert; the (attempt-incremented) envelope was resaved in place
+    /// so it is retried on a later iteration
+    InsertFailed { record_count: usize },
+    /// Batch hit a permanent ClickHouse error or exhausted its replay-attempt budget,
+    /// and was moved to `quarantine/` instead of being retried again
+    Quarantined { record_count: usize },
+}
+
+/// Replays a single batch: load it (from local disk, or from the remote tier via its
+/// stub), insert with retries, and delete on success.
 ///
-/// This function is called once during service startup to replay any batches
-/// that were persisted to DLQ during previous runs.
-pub async fn replay_on_startup<F, R, E>(
-    client: &Client,
-    config: &DlqConfig,
-    table_name: &str,
-) -> Result<ReplayStats, DlqError>
+/// On failure, classifies the ClickHouse error as transient or permanent. Permanent
+/// errors (and batches that have exhausted `max_replay_attempts`) are quarantined
+/// immediately instead of being retried forever; transient failures bump the batch's
+/// `retry_count`. A batch that came from the remote tier and isn't replayed this
+/// attempt is drained down to local disk instead of being re-uploaded: a replay pass
+/// running at all means local pressure has eased, so there's no reason to keep paying
+/// for object storage on a batch we're actively retrying.
+///
+/// Runs as the body of a `JoinSet` task so many batches can be in flight at once,
+/// bounded by the caller's concurrency cap.
+pub(crate) async fn replay_one_batch<F, R, E>(
+    client: Client,
+    batch_path: PathBuf,
+    table_name: String,
+    base_path: PathBuf,
+    max_retries: u32,
+    max_replay_attempts: u32,
+    object_store: Option<Arc<dyn ObjectStore>>,
+) -> BatchReplayOutcome
 where
     F: DatabaseRow<R, E> + Send + 'static + Clone,
     R: InputData,
     E: EnrichmentData,
 {
-    if !config.enabled {
-        return Ok(ReplayStats::default());
-    }
-
-    info!(table = %table_name, "Starting DLQ replay on startup");
-
-    let batches = storage::list_batches(&config.base_path, table_name).await?;
-    let mut stats = ReplayStats {
-        replayed: 0,
-        failed_batches: 0,
-        failed_records: 0,
-    };
-
-    if batches.is_empty() {
-        info!(table = %table_name, "No DLQ batches to replay");
-        return Ok(stats);
-    }
-
-    info!(
-        table = %table_name,
-        batch_count = batches.len(),
-        "Found batches to replay"
-    );
-
-    for batch_path in batches {
-        // Load the batch
-        let batch: BatchEnvelope<F> = match storage::load_batch(&batch_path).await {
-            Ok(b) => b,
+    let remote_stub = if storage::is_remote_stub(&batch_path) {
+        match storage::load_remote_stub(&batch_path).await {
+            Ok(stub) => Some(stub),
             Err(e) => {
                 error!(
                     path = %batch_path.display(),
                     error = %e,
-                    "Failed to load batch, skipping"
+                    "Failed to load remote batch stub, skipping"
                 );
-                stats.failed_batches += 1;
-                continue;
+                return BatchReplayOutcome {
+                    batch_path,
+                    result: BatchReplayResult::LoadFailed,
+                };
             }
-        };
+        }
+    } else {
+        None
+    };
 
-        // Try to insert with retries
-        match insert_batch_with_retries(client, &batch.records, table_name, 5).await {
-            Ok(_) => {
-                // Successfully replayed, delete the batch file
-                if let Err(e) = storage::delete_batch(&batch_path).await {
-                    warn!(
+    let mut batch: BatchEnvelope<F> = match &remote_stub {
+        Some(stub) => {
+            let Some(store) = object_store.as_deref() else {
+                warn!(
+                    path = %batch_path.display(),
+                    bucket = %stub.bucket,
+                    "Remote DLQ batch found but no object store is configured, skipping"
+                );
+                return BatchReplayOutcome {
+                    batch_path,
+                    result: BatchReplayResult::LoadFailed,
+                };
+            };
+            match storage::load_remote_batch(stub, store).await {
+                Ok(b) => b,
+                Err(e) => {
+                    error!(
                         path = %batch_path.display(),
                         error = %e,
-                        "Failed to delete replayed batch file"
+                        "Failed to fetch remote batch, skipping"
                     );
+                    return BatchReplayOutcome {
+                        batch_path,
+                        result: BatchReplayResult::LoadFailed,
+                    };
                 }
-                stats.replayed += batch.record_count;
-                info!(
+            }
+        }
+        None => match storage::load_batch(&batch_path).await {
+            Ok(b) => b,
+            Err(e) => {
+                error!(
                     path = %batch_path.display(),
-                    records = batch.record_count,
-                    "Successfully replayed batch"
+                    error = %e,
+                    "Failed to load batch, skipping"
                 );
+                // A checksum mismatch or malformed JSON means the file itself is
+                // corrupt and will never load, so keep it from blocking replay of
+                // every healthy batch behind it on every future pass.
+                if matches!(e, DlqError::ChecksumMismatch { .. } | DlqError::Serialization(_)) {
+                    if let Err(qe) = storage::quarantine_batch(&batch_path, &base_path).await {
+                        error!(
+                            path = %batch_path.display(),
+                            error = %qe,
+                            "Failed to quarantine corrupt batch"
+                        );
+                    }
+                }
+                return BatchReplayOutcome {
+                    batch_path,
+                    result: BatchReplayResult::LoadFailed,
+                };
             }
-            Err(e) => {
+        },
+    };
+
+    match insert_batch_with_retries(&client, &batch.records, &table_name, max_retries).await {
+        Ok(_) => {
+            if let Some(stub) = &remote_stub {
+                if let Some(store) = object_store.as_deref() {
+                    if let Err(e) = store.delete(&stub.bucket, &stub.key).await {
+                        warn!(
+                            bucket = %stub.bucket,
+                            key = %stub.key,
+                            error = %e,
+                            "Failed to delete replayed batch from object storage"
+                        );
+                    }
+                }
+            }
+            if let Err(e) = storage::delete_batch(&batch_path).await {
                 warn!(
                     path = %batch_path.display(),
                     error = %e,
-                    "Failed to replay batch, will retry later"
+                    "Failed to delete replayed batch file"
                 );
-                stats.failed_records += batch.record_count;
+            }
+            info!(
+                path = %batch_path.display(),
+                records = batch.record_count,
+                "Successfully replayed batch"
+            );
+            BatchReplayOutcome {
+                batch_path,
+                result: BatchReplayResult::Replayed {
+                    record_count: batch.record_count,
+                },
             }
         }
-    }
-
-    info!(
-        table = %table_name,
-        replayed = stats.replayed,
-        failed_batches = stats.failed_batches,
-        failed_records = stats.failed_records,
-        "DLQ startup replay completed"
-    );
+        Err(e) => {
+            batch.retry_count += 1;
+            let is_permanent = storage::classify_clickhouse_error(&e) == storage::ErrorClass::Permanent;
+            let exhausted = batch.retry_count >= max_replay_attempts;
 
-    Ok(stats)
-}
-
-/// Background task that continuously replays batches at a configured interval
-///
-/// This function includes panic recovery - if the replay loop panics, it will
-/// automatically restart with exponential backoff (up to 5 minutes).
-pub async fn start_replay_loop<F, R, E>(
-    client: Client,
-    config: Arc<DlqConfig>,
-    table_name: String,
-) where
-    F: DatabaseRow<R, E> + Send + Sync + 'static + Clone,
-    R: InputData,
-    E: EnrichmentData,
-{
-    if !config.enabled {
-        info!(table = %table_name, "DLQ replay loop disabled");
-        return;
-    }
-
-    let mut restart_count = 0u32;
-    let max_backoff_secs = 300; // 5 minutes
-
-    loop {
-        info!(
-            table = %table_name,
-            interval_secs = config.replay_interval_secs,
-            restart_count = restart_count,
-            "Starting DLQ replay loop"
-        );
-
-        // Spawn the actual replay loop in a nested task to catch panics
-        let task_client = client.clone();
-        let task_config = config.clone();
-        let task_table = table_name.clone();
+            if is_permanent || exhausted {
+                warn!(
+                    path = %batch_path.display(),
+                    error = %e,
+                    permanent = is_permanent,
+                    retry_count = batch.retry_count,
+                    "Quarantining batch that cannot be replayed"
+                );
+                // Quarantine always operates on a local file, so a remote batch is
+                // drained to local disk first and its remote copy cleaned up.
+                let quarantine_source = match &remote_stub {
+                    Some(stub) => match storage::persist_envelope_locally(&batch, &base_path).await
+                    {
+                        Ok(local_path) => {
+                            if let Some(store) = object_store.as_deref() {
+                                let _ = store.delete(&stub.bucket, &stub.key).await;
+                            }
+                            let _ = storage::delete_batch(&batch_path).await;
+                            local_path
+                        }
+                        Err(e) => {
+                            error!(
+                                path = %batch_path.display(),
+                                error = %e,
+                                "Failed to drain remote batch to local disk for quarantine"
+                            );
+                            batch_path.clone()
+                        }
+                    },
+                    None => batch_path.clone(),
+                };
+                if let Err(qe) = storage::quarantine_batch(&quarantine_source, &base_path).await {
+                    error!(
+                        path = %quarantine_source.display(),
+                        error = %qe,
+                        "Failed to quarantine batch"
+                    );
+                }
+                return BatchReplayOutcome {
+                    batch_path,
+                    result: BatchReplayResult::Quarantined {
+                        record_count: batch.record_count,
+                    },
+                };
+            }
 
-        let handle = tokio::spawn(async move {
-            replay_loop_inner::<F, R, E>(task_client, task_config, task_table).await
-        });
+            warn!(
+                path = %batch_path.display(),
+                error = %e,
+                retry_count = batch.retry_count,
+                "Failed to replay batch, will retry later"
+            );
 
-        // Wait for the task to complete (either panic or normal exit)
-        match handle.await {
-            Ok(_) => {
-                // Normal exit (should never happen for infinite loop)
-                warn!(table = %table_name, "DLQ replay loop exited normally (unexpected)");
+            match &remote_stub {
+                Some(stub) => {
+                    // Drain back to local disk rather than re-uploading: a replay pass
+                    // running at all means local pressure has already eased.
+                    match storage::persist_envelope_locally(&batch, &base_path).await {
+                        Ok(_) => {
+                            if let Some(store) = object_store.as_deref() {
+                                if let Err(e) = store.delete(&stub.bucket, &stub.key).await {
+                                    warn!(
+                                        bucket = %stub.bucket,
+                                        key = %stub.key,
+                                        error = %e,
+                                        "Failed to delete drained batch from object storage"
+                                    );
+                                }
+                            }
+                            if let Err(e) = storage::delete_batch(&batch_path).await {
+                                warn!(
+                                    path = %batch_path.display(),
+                                    error = %e,
+                                    "Failed to delete remote batch stub after draining"
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            error!(
+                                path = %batch_path.display(),
+                                error = %e,
+                                "Failed to drain remote batch to local disk, leaving it remote"
+                            );
+                        }
+                    }
+                }
+                None => {
+                    if let Err(se) = storage::resave_batch(&batch_path, &batch).await {
+                        warn!(
+                            path = %batch_path.display(),
+                            error = %se,
+                            "Failed to persist updated retry count on batch"
+                        );
+                    }
+                }
             }
-            Err(e) => {
-                // Task panicked
-                error!(
-                    table = %table_name,
-                    error = %e,
-                    restart_count = restart_count,
-                    "DLQ replay loop panicked, will restart"
-                );
+
+            BatchReplayOutcome {
+                batch_path,
+                result: BatchReplayResult::InsertFailed {
+                    record_count: batch.record_count,
+                },
             }
         }
-
-        // Exponential backoff before restart (capped at max_backoff_secs)
-        restart_count += 1;
-        let backoff_secs = (2u64.pow(restart_count).min(max_backoff_secs as u64)) as u64;
-        warn!(
-            table = %table_name,
-            backoff_secs = backoff_secs,
-            "Waiting before restarting DLQ replay loop"
-        );
-        tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
     }
 }
 
-/// Inner replay loop that runs the actual replay logic
-async fn replay_loop_inner<F, R, E>(
-    client: Client,
-    config: Arc<DlqConfig>,
-    table_name: String,
-) where
-    F: DatabaseRow<R, E> + Send + Sync + 'static + Clone,
+/// Drains a backlog of batch paths through a bounded `JoinSet`, folding each completed
+/// task's outcome into `ReplayStats` as it joins. At most `max_concurrent` replay tasks
+/// are in flight at once, which provides backpressure against ClickHouse.
+async fn replay_batches_bounded<F, R, E>(
+    client: &Client,
+    table_name: &str,
+    base_path: &std::path::Path,
+    batch_paths: Vec<PathBuf>,
+    max_concurrent: usize,
+    max_retries: u32,
+    max_replay_attempts: u32,
+    object_store: Option<&Arc<dyn ObjectStore>>,
+) -> ReplayStats
+where
+    F: DatabaseRow<R, E> + Send + 'static + Clone,
     R: InputData,
     E: EnrichmentData,
 {
-    let mut tick = interval(Duration::from_secs(config.replay_interval_secs));
+    let mut stats = ReplayStats::default();
+    let mut join_set: JoinSet<BatchReplayOutcome> = JoinSet::new();
+    let mut remaining = batch_paths.into_iter();
+    let max_concurrent = max_concurrent.max(1);
 
-    loop {
-        tick.tick().await;
+    let spawn_next =
+        |join_set: &mut JoinSet<BatchReplayOutcome>, remaining: &mut std::vec::IntoIter<PathBuf>| {
+            if let Some(batch_path) = remaining.next() {
+                let client = client.clone();
+                let table_name = table_name.to_string();
+                let base_path = base_path.to_path_buf();
+                let object_store = object_store.cloned();
+                join_set.spawn(replay_one_batch::<F, R, E>(
+                    client,
+                    batch_path,
+                    table_name,
+                    base_path,
+                    max_retries,
+                    max_replay_attempts,
+                    object_store,
+                ));
+            }
+        };
 
-        match replay_iteration::<F, R, E>(&client, &config, &table_name).await {
-            Ok(stats) => {
-                if stats.replayed > 0 || stats.failed_batches > 0 || stats.failed_records > 0 {
-                    info!(
-                        table = %table_name,
-                        replayed = stats.replayed,
-                        failed_batches = stats.failed_batches,
-                        failed_records = stats.failed_records,
-                        "DLQ replay iteration completed"
-                    );
+    for _ in 0..max_concurrent {
+        spawn_next(&mut join_set, &mut remaining);
+    }
+
+    while let Some(joined) = join_set.join_next().await {
+        match joined {
+            Ok(outcome) => match outcome.result {
+                BatchReplayResult::LoadFailed => stats.failed_batches += 1,
+                BatchReplayResult::Replayed { record_count } => stats.replayed += record_count,
+                BatchReplayResult::InsertFailed { record_count } => {
+                    stats.failed_records += record_count
                 }
-            }
+                BatchReplayResult::Quarantined { record_count } => {
+                    stats.failed_records += record_count;
+                    stats.quarantined += 1;
+                }
+            },
             Err(e) => {
-                error!(table = %table_name, error = %e, "DLQ replay iteration failed");
+                error!(error = %e, "DLQ replay task panicked");
+                stats.failed_batches += 1;
             }
         }
+
+        spawn_next(&mut join_set, &mut remaining);
     }
+
+    stats
 }
 
-/// Performs a single replay iteration for a table
-async fn replay_iteration<F, R, E>(
+/// Replays all persisted batches for a given table on startup
+///
+/// This function is called once during service startup to replay any batches
+/// that were persisted to DLQ during previous runs.
+pub async fn replay_on_startup<F, R, E>(
     client: &Client,
     config: &DlqConfig,
     table_name: &str,
+    object_store: Option<&Arc<dyn ObjectStore>>,
 ) -> Result<ReplayStats, DlqError>
 where
     F: DatabaseRow<R, E> + Send + 'static + Clone,
     R: InputData,
     E: EnrichmentData,
 {
-    let batches = storage::list_batches(&config.base_path, table_name).await?;
-    let mut stats = ReplayStats::default();
+    if !config.enabled {
+        return Ok(ReplayStats::default());
+    }
 
-    for batch_path in batches.iter().take(10) {
-        // Process max 10 batches per iteration
-        let batch: BatchEnvelope<F> = match storage::load_batch(batch_path).await {
-            Ok(b) => b,
-            Err(e) => {
-                error!(
-                    path = %batch_path.display(),
-                    error = %e,
-                    "Failed to load batch"
-                );
-                stats.failed_batches += 1;
-                continue;
-            }
-        };
+    info!(table = %table_name, "Starting DLQ replay on startup");
 
-        match insert_batch_with_retries(client, &batch.records, table_name, 3).await {
-            Ok(_) => {
-                storage::delete_batch(batch_path).await?;
-                stats.replayed += batch.record_count;
-            }
-            Err(e) => {
-                warn!(
-                    path = %batch_path.display(),
-                    error = %e,
-                    "Failed to replay batch"
-                );
-                stats.failed_records += batch.record_count;
-            }
-        }
+    let batches = storage::list_batches(&config.base_path, table_name).await?;
+
+    if batches.is_empty() {
+        info!(table = %table_name, "No DLQ batches to replay");
+        return Ok(ReplayStats::default());
     }
 
+    info!(
+        table = %table_name,
+        batch_count = batches.len(),
+        max_concurrent = config.max_concurrent_replays,
+        "Found batches to replay"
+    );
+
+    let stats = replay_batches_bounded::<F, R, E>(
+        client,
+        table_name,
+        &config.base_path,
+        batches,
+        config.max_concurrent_replays,
+        5,
+        config.max_replay_attempts,
+        object_store,
+    )
+    .await;
+
+    info!(
+        table = %table_name,
+        replayed = stats.replayed,
+        failed_batches = stats.failed_batches,
+        failed_records = stats.failed_records,
+        quarantined = stats.quarantined,
+        "DLQ startup replay completed"
+    );
+
     Ok(stats)
 }
 
 /// Inserts a batch into ClickHouse with retry logic
-async fn insert_batch_with_retries<F>(
+pub(crate) async fn insert_batch_with_retries<F>(
     client: &Client,
     records: &[F],
     table_name: &str,
@@ -275,6 +432,14 @@ where
         match result {
             Ok(_) => return Ok(()),
             Err(e) => {
+                // Permanent errors (schema mismatch, malformed row, type error) won't
+                // succeed on retry, so fail fast and let the caller quarantine the batch
+                // instead of burning through the backoff schedule for nothing.
+                if storage::classify_clickhouse_error(&e) == storage::ErrorClass::Permanent {
+                    warn!(error = %e, "Permanent ClickHouse error, not retrying");
+                    return Err(e);
+                }
+
                 retry_count += 1;
                 if retry_count >= max_retries {
                     return Err(e);
@@ -0,0 +1,654 @@
+//! Unified, prioritized scheduler over a table's pending DLQ batches.
+//!
+//! Replay (`dlq::replay`) and TTL/quota cleanup used to run as independent
+//! timers, so a cleanup pass could delete a batch replay was mid-retry on, and an
+//! expensive table's backlog could starve a cheap table's behind it on a shared
+//! cleanup loop. This module instead drives one loop per table that, for each pending
+//! batch, asks a prioritized list of `BatchHandler`s whether they want it and dispatches
+//! to the first that does. Quota enforcement and replay are registered ahead of TTL
+//! cleanup, so a batch that's both over-quota-evictable and still replayable is evicted
+//! (freeing disk) rather than replayed, and a batch within quota is replayed before it's
+//! ever considered for TTL expiry.
+//!
+//! This mirrors the batch-handler/scheduler pattern common in task-queue systems: new
+//! DLQ behaviors (e.g. a future remote-tier drain) can be added as another `BatchHandler`
+//! without introducing another standalone timer.
+
+use crate::dlq::archive::{self, ArchiveMode};
+use crate::dlq::backend::DlqBackend;
+use crate::dlq::object_store::ObjectStore;
+use crate::dlq::replay::{self, BatchReplayResult};
+use crate::dlq::storage::{self, DlqError};
+use crate::dlq::types::BatchEnvelope;
+use crate::dlq::DlqConfig;
+use crate::traits::{DatabaseRow, EnrichmentData, InputData};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use clickhouse::Client;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use tokio::time::interval;
+use tracing::{error, info, warn};
+
+/// A pluggable unit of work the scheduler can dispatch a pending batch to.
+///
+/// `accept` is synchronous and cheap so the scheduler can ask every handler about every
+/// batch without extra I/O; any per-pass state a handler needs (e.g. current disk usage)
+/// should be computed once in `refresh` rather than inside `accept`.
+#[async_trait]
+pub trait BatchHandler: Send + Sync {
+    /// Short name used in logs to say which handler acted on a batch.
+    fn name(&self) -> &'static str;
+
+    /// Refreshes any state `accept` depends on. Called once per scheduler pass, before
+    /// any batch in that pass is considered. Default no-op for stateless handlers.
+    async fn refresh(&self, _config: &DlqConfig) -> Result<(), DlqError> {
+        Ok(())
+    }
+
+    /// Whether this handler wants to process `batch_meta` this pass.
+    fn accept(&self, batch_meta: &BatchEnvelope<()>) -> bool;
+
+    /// Processes the batch identified by `batch_id` (an opaque id from the scheduler's
+    /// `DlqBackend`, see `DlqScheduler::run_iteration`). Only called immediately after
+    /// `accept` returned `true` for the same batch.
+    async fn handle(&self, batch_id: &str, config: &DlqConfig) -> Result<(), DlqError>;
+}
+
+/// Evicts the oldest pending batches once on-disk usage exceeds `max_disk_mb`,
+/// re-checking usage before each eviction so it stops as soon as it's back under
+/// budget instead of draining every batch `refresh` found over budget.
+///
+/// Usage and deletion go through an injected `DlqBackend` rather than `storage::`
+/// directly, so quota enforcement works the same way regardless of whether batches
+/// live on local disk or in an object store (see `DlqConfig::backend`).
+///
+/// When `DlqConfig::archive_mode` is `Archive`, an evicted batch is uploaded to
+/// `DlqConfig::archive_bucket` via `object_store` instead of being deleted outright (see
+/// `archive::archive_batch`); `object_store` here is the same handle
+/// `scheduler::ReplayHandler` uses for the remote-spill tier, reused for archival too.
+pub struct QuotaEnforcementHandler {
+    backend: Arc<dyn DlqBackend>,
+    object_store: Option<Arc<dyn ObjectStore>>,
+    over_budget: AtomicBool,
+}
+
+impl QuotaEnforcementHandler {
+    pub fn new(backend: Arc<dyn DlqBackend>, object_store: Option<Arc<dyn ObjectStore>>) -> Self {
+        Self {
+            backend,
+            object_store,
+            over_budget: AtomicBool::new(false),
+        }
+    }
+}
+
+#[async_trait]
+impl BatchHandler for QuotaEnforcementHandler {
+    fn name(&self) -> &'static str {
+        "quota_enforcement"
+    }
+
+    async fn refresh(&self, config: &DlqConfig) -> Result<(), DlqError> {
+        let usage = self.backend.total_usage().await?;
+        let max_bytes = config.max_disk_mb * 1024 * 1024;
+        self.over_budget.store(usage > max_bytes, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn accept(&self, _batch_meta: &BatchEnvelope<()>) -> bool {
+        self.over_budget.load(Ordering::Relaxed)
+    }
+
+    async fn handle(&self, batch_id: &str, config: &DlqConfig) -> Result<(), DlqError> {
+        let usage = self.backend.total_usage().await?;
+        let max_bytes = config.max_disk_mb * 1024 * 1024;
+        if usage <= max_bytes {
+            self.over_budget.store(false, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        if config.archive_mode == ArchiveMode::Archive {
+            if let (Some(store), Some(bucket)) = (&self.object_store, &config.archive_bucket) {
+                // Archiving reads the batch straight off local disk, same as the
+                // filesystem backend's own storage; this only round-trips correctly
+                // today for a `batch_id` that's actually a filesystem path (see
+                // `backend::build_backend`'s doc comment for why the RocksDB backend is
+                // never selected for that reason).
+                let key = archive::archive_batch(store, bucket, Path::new(batch_id)).await?;
+                info!(
+                    %batch_id,
+                    bucket = %bucket,
+                    %key,
+                    "Archived oldest pending batch instead of deleting it to enforce DLQ disk quota"
+                );
+                return Ok(());
+            }
+            warn!(
+                %batch_id,
+                "DLQ archive mode is enabled but no archive bucket/object store is configured, falling back to hard delete"
+            );
+        }
+
+        self.backend.delete_batch(batch_id).await?;
+        info!(%batch_id, "Deleted oldest pending batch to enforce DLQ disk quota");
+        Ok(())
+    }
+}
+
+/// Deletes batches older than `DlqConfig::batch_ttl_hours`. The cutoff is computed once
+/// per pass in `refresh` (rather than per batch in `accept`, which only sees the batch's
+/// own metadata) and cached for the rest of that pass.
+///
+/// Deletion goes through an injected `DlqBackend`, same reasoning as
+/// `QuotaEnforcementHandler`. Also shares that handler's `archive_mode` behavior: an
+/// expired batch is archived instead of deleted when `DlqConfig::archive_mode` is
+/// `Archive` and an archive bucket/object store are configured.
+pub struct TtlCleanupHandler {
+    backend: Arc<dyn DlqBackend>,
+    object_store: Option<Arc<dyn ObjectStore>>,
+    cutoff_unix_secs: AtomicI64,
+}
+
+impl TtlCleanupHandler {
+    pub fn new(backend: Arc<dyn DlqBackend>, object_store: Option<Arc<dyn ObjectStore>>) -> Self {
+        Self {
+            backend,
+            object_store,
+            cutoff_unix_secs: AtomicI64::new(i64::MIN),
+        }
+    }
+}
+
+#[async_trait]
+impl BatchHandler for TtlCleanupHandler {
+    fn name(&self) -> &'static str {
+        "ttl_cleanup"
+    }
+
+    async fn refresh(&self, config: &DlqConfig) -> Result<(), DlqError> {
+        let cutoff = Utc::now() - Duration::hours(config.batch_ttl_hours as i64);
+        self.cutoff_unix_secs
+            .store(cutoff.timestamp(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn accept(&self, batch_meta: &BatchEnvelope<()>) -> bool {
+        batch_meta.timestamp.timestamp() < self.cutoff_unix_secs.load(Ordering::Relaxed)
+    }
+
+    async fn handle(&self, batch_id: &str, config: &DlqConfig) -> Result<(), DlqError> {
+        if config.archive_mode == ArchiveMode::Archive {
+            if let (Some(store), Some(bucket)) = (&self.object_store, &config.archive_bucket) {
+                let key = archive::archive_batch(store, bucket, Path::new(batch_id)).await?;
+                info!(%batch_id, bucket = %bucket, %key, "Archived expired batch instead of deleting it");
+                return Ok(());
+            }
+            warn!(
+                %batch_id,
+                "DLQ archive mode is enabled but no archive bucket/object store is configured, falling back to hard delete"
+            );
+        }
+
+        self.backend.delete_batch(batch_id).await?;
+        info!(%batch_id, "Deleted expired batch");
+        Ok(())
+    }
+}
+
+/// Replays a single table's batches, mirroring `dlq::replay::replay_one_batch`'s
+/// load-insert-or-quarantine logic. One instance is registered per table, since the
+/// ClickHouse row type is monomorphized per table the same way `replay::replay_on_startup` is.
+pub struct ReplayHandler<F, R, E> {
+    client: Client,
+    table_name: String,
+    object_store: Option<Arc<dyn ObjectStore>>,
+    _marker: PhantomData<fn() -> (F, R, E)>,
+}
+
+impl<F, R, E> ReplayHandler<F, R, E> {
+    pub fn new(client: Client, table_name: String, object_store: Option<Arc<dyn ObjectStore>>) -> Self {
+        Self {
+            client,
+            table_name,
+            object_store,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<F, R, E> BatchHandler for ReplayHandler<F, R, E>
+where
+    F: DatabaseRow<R, E> + Send + 'static + Clone,
+    R: InputData,
+    E: EnrichmentData,
+{
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn accept(&self, batch_meta: &BatchEnvelope<()>) -> bool {
+        batch_meta.table_name == self.table_name
+    }
+
+    async fn handle(&self, batch_id: &str, config: &DlqConfig) -> Result<(), DlqError> {
+        // `replay_one_batch` reads the full typed envelope straight off local disk, so
+        // this only works today for a `batch_id` that's actually a filesystem path (see
+        // `backend::build_backend`'s doc comment: replay, like archive-on-eviction, has
+        // not moved onto the backend abstraction).
+        let batch_path = PathBuf::from(batch_id);
+        let outcome = replay::replay_one_batch::<F, R, E>(
+            self.client.clone(),
+            batch_path.clone(),
+            self.table_name.clone(),
+            config.base_path.clone(),
+            3,
+            config.max_replay_attempts,
+            self.object_store.clone(),
+        )
+        .await;
+
+        match outcome.result {
+            BatchReplayResult::Replayed { record_count } => {
+                info!(path = %batch_path.display(), records = record_count, "Replayed batch via scheduler");
+            }
+            BatchReplayResult::InsertFailed { .. } => {
+                // Already logged and resaved with a bumped retry count inside
+                // `replay_one_batch`; nothing further for the scheduler to do.
+            }
+            BatchReplayResult::Quarantined { .. } => {
+                // Already quarantined inside `replay_one_batch`.
+            }
+            BatchReplayResult::LoadFailed => {
+                warn!(path = %batch_path.display(), "Scheduler replay handler could not load batch");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a prioritized list of `BatchHandler`s against one table's pending batches.
+///
+/// Pending batches are discovered through `backend` rather than `storage::list_batches`
+/// directly, so a table configured for a non-filesystem `DlqConfig::backend` actually
+/// gets its batches enumerated and identified the same way they were written (see
+/// `backend::build_backend`); the `batch_id`s handed to each `BatchHandler::handle` are
+/// whatever that backend considers a batch's identity, not necessarily a filesystem path.
+pub struct DlqScheduler {
+    handlers: Vec<Box<dyn BatchHandler>>,
+    backend: Arc<dyn DlqBackend>,
+}
+
+impl DlqScheduler {
+    pub fn new(handlers: Vec<Box<dyn BatchHandler>>, backend: Arc<dyn DlqBackend>) -> Self {
+        Self { handlers, backend }
+    }
+
+    /// Runs one scheduling pass over `table_name`'s pending batches: refreshes every
+    /// handler's per-pass state, then for each batch (oldest first, per
+    /// `backend.list_batches`) dispatches to the first handler that accepts it.
+    pub async fn run_iteration(&self, config: &DlqConfig, table_name: &str) -> Result<(), DlqError> {
+        for handler in &self.handlers {
+            handler.refresh(config).await?;
+        }
+
+        let batch_ids = self.backend.list_batches(table_name).await?;
+
+        for batch_id in batch_ids {
+            let meta = match self.backend.batch_metadata(&batch_id).await {
+                Ok(meta) => meta,
+                Err(e) => {
+                    warn!(
+                        %batch_id,
+                        error = %e,
+                        "Skipping batch with unreadable metadata"
+                    );
+                    continue;
+                }
+            };
+
+            let Some(handler) = self.handlers.iter().find(|h| h.accept(&meta)) else {
+                continue;
+            };
+
+            if let Err(e) = handler.handle(&batch_id, config).await {
+                warn!(
+                    %batch_id,
+                    handler = handler.name(),
+                    error = %e,
+                    "DLQ scheduler handler failed"
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Background task that ticks a `DlqScheduler` for one table at `replay_interval_secs`,
+/// restarting with exponential backoff (up to 5 minutes) if it panics. Replaces the
+/// former independent replay loop and cleanup-timer pair; every DLQ table runs one.
+pub async fn start_scheduler_loop(
+    scheduler: Arc<DlqScheduler>,
+    config: Arc<DlqConfig>,
+    table_name: String,
+) {
+    if !config.enabled {
+        info!(table = %table_name, "DLQ scheduler loop disabled");
+        return;
+    }
+
+    let mut restart_count = 0u32;
+    let max_backoff_secs = 300;
+
+    loop {
+        info!(
+            table = %table_name,
+            interval_secs = config.replay_interval_secs,
+            restart_count = restart_count,
+            "Starting DLQ scheduler loop"
+        );
+
+        let task_scheduler = scheduler.clone();
+        let task_config = config.clone();
+        let task_table = table_name.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut tick = interval(tokio::time::Duration::from_secs(
+                task_config.replay_interval_secs,
+            ));
+            loop {
+                tick.tick().await;
+                if let Err(e) = task_scheduler.run_iteration(&task_config, &task_table).await {
+                    error!(table = %task_table, error = %e, "DLQ scheduler iteration failed");
+                }
+            }
+        });
+
+        match handle.await {
+            Ok(_) => warn!(table = %table_name, "DLQ scheduler loop exited normally (unexpected)"),
+            Err(e) => error!(
+                table = %table_name,
+                error = %e,
+                restart_count = restart_count,
+                "DLQ scheduler loop panicked, will restart"
+            ),
+        }
+
+        restart_count += 1;
+        let backoff_secs = (2u64.pow(restart_count).min(max_backoff_secs as u64)) as u64;
+        warn!(table = %table_name, backoff_secs = backoff_secs, "Waiting before restarting DLQ scheduler loop");
+        tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
+    }
+}
+
+/// Builds `cutoff`-based handlers shared across every table's scheduler: quota
+/// enforcement first, then the caller's table-specific replay handler, then TTL
+/// cleanup last. `backend` drives quota/TTL bookkeeping (see `backend::build_backend`);
+/// replay keeps reading full envelopes straight off disk/`object_store` regardless of
+/// which backend is selected, since it needs the concrete row type.
+pub fn default_handlers<F, R, E>(
+    client: Client,
+    table_name: String,
+    object_store: Option<Arc<dyn ObjectStore>>,
+    backend: Arc<dyn DlqBackend>,
+) -> Vec<Box<dyn BatchHandler>>
+where
+    F: DatabaseRow<R, E> + Send + 'static + Clone,
+    R: InputData,
+    E: EnrichmentData,
+{
+    vec![
+        Box::new(QuotaEnforcementHandler::new(
+            backend.clone(),
+            object_store.clone(),
+        )),
+        Box::new(ReplayHandler::<F, R, E>::new(
+            client,
+            table_name,
+            object_store.clone(),
+        )),
+        Box::new(TtlCleanupHandler::new(backend, object_store)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlq::init_directories;
+    use tempfile::TempDir;
+
+    struct AlwaysAccept;
+
+    #[async_trait]
+    impl BatchHandler for AlwaysAccept {
+        fn name(&self) -> &'static str {
+            "always_accept"
+        }
+
+        fn accept(&self, _batch_meta: &BatchEnvelope<()>) -> bool {
+            true
+        }
+
+        async fn handle(&self, batch_id: &str, _config: &DlqConfig) -> Result<(), DlqError> {
+            storage::delete_batch(Path::new(batch_id)).await
+        }
+    }
+
+    struct NeverAccept;
+
+    #[async_trait]
+    impl BatchHandler for NeverAccept {
+        fn name(&self) -> &'static str {
+            "never_accept"
+        }
+
+        fn accept(&self, _batch_meta: &BatchEnvelope<()>) -> bool {
+            false
+        }
+
+        async fn handle(&self, _batch_id: &str, _config: &DlqConfig) -> Result<(), DlqError> {
+            panic!("never_accept handler should never be dispatched to");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_dispatches_to_first_accepting_handler() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        init_directories(&config).await.unwrap();
+
+        let backend = crate::dlq::backend::build_backend(&config, None);
+        crate::dlq::persist_batch(&vec![1, 2, 3], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+
+        let scheduler = DlqScheduler::new(
+            vec![Box::new(NeverAccept), Box::new(AlwaysAccept)],
+            backend,
+        );
+        scheduler
+            .run_iteration(&config, "mlop_metrics")
+            .await
+            .unwrap();
+
+        let remaining = storage::list_batches(&config.base_path, "mlop_metrics")
+            .await
+            .unwrap();
+        assert!(remaining.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_skips_batch_no_handler_accepts() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        init_directories(&config).await.unwrap();
+
+        let backend = crate::dlq::backend::build_backend(&config, None);
+        crate::dlq::persist_batch(&vec![1], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+
+        let scheduler = DlqScheduler::new(vec![Box::new(NeverAccept)], backend);
+        scheduler
+            .run_iteration(&config, "mlop_metrics")
+            .await
+            .unwrap();
+
+        let remaining = storage::list_batches(&config.base_path, "mlop_metrics")
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_quota_enforcement_handler_accepts_only_when_over_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        init_directories(&config).await.unwrap();
+
+        let backend: Arc<dyn DlqBackend> = Arc::new(crate::dlq::backend::FilesystemDlqBackend::new(
+            config.base_path.clone(),
+            false,
+        ));
+        crate::dlq::persist_batch(&vec![1, 2, 3], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+
+        let handler = QuotaEnforcementHandler::new(backend, None);
+
+        let meta = BatchEnvelope {
+            table_name: "mlop_metrics".to_string(),
+            timestamp: Utc::now(),
+            record_count: 3,
+            records: Vec::new(),
+            retry_count: 0,
+            checksum: None,
+        };
+
+        // One small batch is well within the default 1024 MB quota.
+        handler.refresh(&config).await.unwrap();
+        assert!(!handler.accept(&meta));
+
+        // Zeroing the quota only after persisting (persist itself enforces it) makes the
+        // stored batch over budget.
+        config.max_disk_mb = 0;
+        handler.refresh(&config).await.unwrap();
+        assert!(handler.accept(&meta));
+    }
+
+    #[tokio::test]
+    async fn test_ttl_cleanup_handler_accepts_expired_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        config.batch_ttl_hours = 24;
+
+        let backend = Arc::new(crate::dlq::backend::FilesystemDlqBackend::new(
+            config.base_path.clone(),
+            false,
+        ));
+        let handler = TtlCleanupHandler::new(backend, None);
+        handler.refresh(&config).await.unwrap();
+
+        let expired = BatchEnvelope {
+            table_name: "mlop_metrics".to_string(),
+            timestamp: Utc::now() - Duration::hours(48),
+            record_count: 1,
+            records: Vec::new(),
+            retry_count: 0,
+            checksum: None,
+        };
+        assert!(handler.accept(&expired));
+
+        let fresh = BatchEnvelope {
+            table_name: "mlop_metrics".to_string(),
+            timestamp: Utc::now(),
+            record_count: 1,
+            records: Vec::new(),
+            retry_count: 0,
+            checksum: None,
+        };
+        assert!(!handler.accept(&fresh));
+    }
+
+    #[tokio::test]
+    async fn test_quota_enforcement_archives_instead_of_deleting_when_archive_mode_enabled() {
+        use crate::dlq::archive::ArchiveMode;
+        use crate::dlq::object_store::InMemoryObjectStore;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        config.archive_mode = ArchiveMode::Archive;
+        config.archive_bucket = Some("dlq-archive".to_string());
+        init_directories(&config).await.unwrap();
+
+        let backend: Arc<dyn DlqBackend> = Arc::new(crate::dlq::backend::FilesystemDlqBackend::new(
+            config.base_path.clone(),
+            false,
+        ));
+        crate::dlq::persist_batch(&vec![1, 2, 3], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+        let batches = storage::list_batches(&config.base_path, "mlop_metrics")
+            .await
+            .unwrap();
+        let batch_path = batches[0].clone();
+        // Zeroed only after persisting, since persist itself enforces the quota.
+        config.max_disk_mb = 0;
+
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemoryObjectStore::new());
+        let handler = QuotaEnforcementHandler::new(backend, Some(store.clone()));
+        handler.refresh(&config).await.unwrap();
+        handler
+            .handle(&batch_path.to_string_lossy(), &config)
+            .await
+            .unwrap();
+
+        assert!(!batch_path.exists());
+        let archived = crate::dlq::archive::list_archived_batches(&store, "dlq-archive", "mlop_metrics")
+            .await
+            .unwrap();
+        assert_eq!(archived.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_cleanup_archives_instead_of_deleting_when_archive_mode_enabled() {
+        use crate::dlq::archive::ArchiveMode;
+        use crate::dlq::object_store::InMemoryObjectStore;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = DlqConfig::for_testing(temp_dir.path().to_path_buf());
+        config.archive_mode = ArchiveMode::Archive;
+        config.archive_bucket = Some("dlq-archive".to_string());
+        init_directories(&config).await.unwrap();
+
+        let backend: Arc<dyn DlqBackend> = Arc::new(crate::dlq::backend::FilesystemDlqBackend::new(
+            config.base_path.clone(),
+            false,
+        ));
+        crate::dlq::persist_batch(&vec![1], "mlop_metrics".to_string(), &config, &backend, None)
+            .await
+            .unwrap();
+        let batches = storage::list_batches(&config.base_path, "mlop_metrics")
+            .await
+            .unwrap();
+        let batch_path = batches[0].clone();
+
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemoryObjectStore::new());
+        let handler = TtlCleanupHandler::new(backend, Some(store.clone()));
+        handler
+            .handle(&batch_path.to_string_lossy(), &config)
+            .await
+            .unwrap();
+
+        assert!(!batch_path.exists());
+        let archived = crate::dlq::archive::list_archived_batches(&store, "dlq-archive", "mlop_metrics")
+            .await
+            .unwrap();
+        assert_eq!(archived.len(), 1);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::dlq::types::BatchEnvelope;
+use crate::dlq::object_store::{ObjectStore, ObjectStoreError};
+use crate::dlq::types::{BatchEnvelope, BatchManifest, CleanupStats, RemoteStub, SpillLocation};
 use chrono::Utc;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
@@ -17,16 +18,157 @@ pub enum DlqError {
     #[error("Disk quota exceeded")]
     DiskQuotaExceeded,
 
+    #[error("Batch checksum mismatch: expected {expected:#010x}, got {actual:#010x}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+
     #[error("DLQ is disabled")]
     Disabled,
+
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] ObjectStoreError),
+}
+
+/// zstd compression level used for DLQ batch files. Chosen for fast compression rather
+/// than maximum ratio, since this runs inline with the ingest failure path.
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
+/// Extension used for zstd-compressed batch files.
+const COMPRESSED_EXTENSION: &str = "json.zst";
+
+/// Extension used for the local stub file left behind when a batch spills to the
+/// remote object-storage tier instead of staying on local disk.
+const REMOTE_STUB_EXTENSION: &str = "remote.json";
+
+/// Extension of the sidecar file that records a direct-I/O batch's true (unpadded)
+/// byte length, since O_DIRECT pads the on-disk file out to a block-size multiple.
+const DIRECT_IO_LEN_SIDECAR_EXTENSION: &str = "len";
+
+/// Device logical block size assumed when it can't be probed from the filesystem.
+const DEFAULT_BLOCK_SIZE: usize = 4096;
+
+/// Extension of the sidecar manifest recording a batch's `created_at`/`size_bytes`/
+/// `row_count`/`source_table`/`retry_count`, so `list_batches` and
+/// `evict_oldest_until_within_budget` don't have to re-derive them from the filename or
+/// re-read the (possibly compressed) batch payload.
+const MANIFEST_EXTENSION: &str = "manifest";
+
+/// Lifetime count of batches moved to quarantine (checksum mismatch, corrupt JSON, or
+/// exhausted replay attempts), incremented by `quarantine_batch`. Process-local; resets
+/// on restart, same as the counters `DlqStats` otherwise expects a caller to track.
+static BATCHES_QUARANTINED_TOTAL: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Current value of the lifetime quarantine counter, for `DlqStats`/health reporting.
+pub fn batches_quarantined_total() -> u64 {
+    BATCHES_QUARANTINED_TOTAL.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Computes the CRC32 of `records`' serialized JSON bytes, used to detect truncation or
+/// bit-rot of a persisted batch file independently of whether the JSON itself still
+/// happens to parse.
+fn compute_checksum<T: Serialize>(records: &[T]) -> Result<u32, DlqError> {
+    let bytes = serde_json::to_vec(records)?;
+    Ok(crc32fast::hash(&bytes))
+}
+
+/// Path of `batch_path`'s sidecar manifest.
+fn manifest_path(batch_path: &Path) -> PathBuf {
+    let mut name = batch_path.as_os_str().to_os_string();
+    name.push(format!(".{MANIFEST_EXTENSION}"));
+    PathBuf::from(name)
+}
+
+/// Atomically writes `manifest` alongside `batch_path`, using the same
+/// write-to-temp-then-rename pattern as the batch file itself.
+async fn write_manifest(batch_path: &Path, manifest: &BatchManifest) -> Result<(), DlqError> {
+    let manifest_data = serde_json::to_vec(manifest)?;
+    let dest = manifest_path(batch_path);
+    let temp_path = PathBuf::from(format!("{}.tmp", dest.display()));
+    fs::write(&temp_path, &manifest_data).await?;
+    fs::rename(&temp_path, &dest).await?;
+    Ok(())
+}
+
+/// Reads `batch_path`'s sidecar manifest, returning `None` if it's missing or corrupt
+/// rather than erroring, so callers can fall back to rebuilding it.
+async fn read_manifest(batch_path: &Path) -> Option<BatchManifest> {
+    let raw = fs::read(manifest_path(batch_path)).await.ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+/// Reconstructs a batch's manifest straight from the batch file itself (its header, via
+/// `peek_batch_meta`, plus its on-disk size), for when the sidecar manifest is missing or
+/// corrupt. Works for remote stubs too, since `peek_batch_meta` already knows how to read
+/// a stub's header.
+async fn build_manifest_from_batch(batch_path: &Path) -> Result<BatchManifest, DlqError> {
+    let size_bytes = fs::metadata(batch_path).await?.len();
+    let meta = peek_batch_meta(batch_path).await?;
+    Ok(BatchManifest {
+        created_at: meta.timestamp,
+        size_bytes,
+        row_count: meta.record_count,
+        source_table: meta.table_name,
+        retry_count: meta.retry_count,
+    })
+}
+
+/// Reads `batch_path`'s manifest, rebuilding and re-persisting it from the batch file
+/// itself if it's missing or corrupt (e.g. the process crashed between writing the batch
+/// and writing its manifest), so an interrupted write never strands a batch without one.
+pub async fn load_or_rebuild_manifest(batch_path: &Path) -> Result<BatchManifest, DlqError> {
+    if let Some(manifest) = read_manifest(batch_path).await {
+        return Ok(manifest);
+    }
+
+    warn!(
+        path = %batch_path.display(),
+        "DLQ batch manifest missing or corrupt, rebuilding from the batch file"
+    );
+    let manifest = build_manifest_from_batch(batch_path).await?;
+    write_manifest(batch_path, &manifest).await?;
+    Ok(manifest)
 }
 
-/// Persists a batch of records to disk as JSON
+/// Serializes `records` into a `BatchEnvelope` (with a checksum) and zstd-compresses it,
+/// without writing anything to disk. This is the shared first half of `persist_batch`'s
+/// pipeline, factored out so `dlq::persist_batch` can hand the resulting bytes to a
+/// `backend::DlqBackend::write_batch` instead of this module always writing straight to
+/// local disk.
+pub fn build_compressed_envelope<T>(records: &[T], table_name: String) -> Result<Vec<u8>, DlqError>
+where
+    T: Serialize + Clone,
+{
+    if records.is_empty() {
+        debug!("Skipping persist of empty batch");
+        return Err(DlqError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Empty batch",
+        )));
+    }
+
+    let envelope = BatchEnvelope {
+        table_name,
+        timestamp: Utc::now(),
+        record_count: records.len(),
+        records: records.to_vec(),
+        retry_count: 0,
+        checksum: Some(compute_checksum(records)?),
+    };
+
+    // Serialize to JSON, then compress. Batches are bounded in size (one ingest flush
+    // worth of records), so this runs inline rather than on a blocking thread pool.
+    let json_data = serde_json::to_vec(&envelope)?;
+    zstd::stream::encode_all(json_data.as_slice(), ZSTD_COMPRESSION_LEVEL).map_err(DlqError::Io)
+}
+
+/// Persists a batch of records to disk as zstd-compressed JSON (`.json.zst`)
 ///
 /// # Arguments
 /// * `records` - The records to persist
 /// * `table_name` - The ClickHouse table name
 /// * `base_path` - Base directory for DLQ storage
+/// * `direct_io` - When set, writes the compressed payload with O_DIRECT (see
+///   `write_batch_contents`) instead of going through the page cache. Automatically
+///   falls back to a buffered write if the filesystem rejects O_DIRECT.
 ///
 /// # Returns
 /// Path to the persisted batch file
@@ -34,6 +176,217 @@ pub async fn persist_batch<T>(
     records: &[T],
     table_name: String,
     base_path: &Path,
+    direct_io: bool,
+) -> Result<PathBuf, DlqError>
+where
+    T: Serialize + Clone,
+{
+    let compressed = build_compressed_envelope(records, table_name.clone())?;
+    write_raw_batch(base_path, &table_name, &compressed, direct_io).await
+}
+
+/// Writes an already-serialized-and-compressed batch payload to disk under a fresh
+/// timestamp-and-UUID filename, using the same atomic write-to-temp-then-rename-then-
+/// fsync pattern `persist_batch` used to inline directly. Used by both `persist_batch`
+/// and `backend::FilesystemDlqBackend::write_batch`, which receives bytes the caller
+/// already built rather than a typed record slice.
+pub async fn write_raw_batch(
+    base_path: &Path,
+    table_name: &str,
+    compressed: &[u8],
+    direct_io: bool,
+) -> Result<PathBuf, DlqError> {
+    let table_dir = base_path.join(table_name);
+    fs::create_dir_all(&table_dir).await?;
+
+    let timestamp = Utc::now();
+    let batch_id = uuid::Uuid::new_v4();
+    let filename = format!(
+        "{}_{}.{}",
+        timestamp.format("%Y-%m-%dT%H-%M-%S%.3f"),
+        batch_id,
+        COMPRESSED_EXTENSION
+    );
+    let file_path = table_dir.join(&filename);
+
+    // Atomic write: write to temp file, then rename
+    let temp_path = table_dir.join(format!("{}.tmp", filename));
+    let used_direct_io = write_batch_contents(&temp_path, compressed, direct_io).await?;
+    fs::rename(&temp_path, &file_path).await?;
+    if used_direct_io {
+        fs::rename(
+            direct_io_len_sidecar_path(&temp_path),
+            direct_io_len_sidecar_path(&file_path),
+        )
+        .await?;
+    }
+    // Fsync the renamed file and its directory entry so the batch survives a crash
+    // immediately after this call returns, rather than whenever the page cache happens
+    // to write back. A "persisted" batch that silently disappears on power loss would
+    // break the DLQ's zero-data-loss promise.
+    fsync_path(&file_path).await?;
+    fsync_path(&table_dir).await?;
+
+    // The caller already built and compressed the envelope, so its header has to be
+    // re-read to build the manifest rather than threaded through as extra arguments.
+    let manifest = build_manifest_from_batch(&file_path).await?;
+    write_manifest(&file_path, &manifest).await?;
+
+    info!(
+        table = %table_name,
+        row_count = manifest.row_count,
+        path = %file_path.display(),
+        compressed_bytes = compressed.len(),
+        direct_io = used_direct_io,
+        "Batch persisted to DLQ"
+    );
+
+    Ok(file_path)
+}
+
+/// Fsyncs a file or directory at `path`, so whatever was just written to it (or renamed
+/// into it) is durable before this call returns. Opening a directory read-only and
+/// syncing it is the standard way to flush a rename's directory-entry update to disk.
+async fn fsync_path(path: &Path) -> Result<(), DlqError> {
+    let file = fs::File::open(path).await?;
+    file.sync_all().await?;
+    Ok(())
+}
+
+/// Path of the sidecar file that records `path`'s true (unpadded) byte length when it
+/// was written via O_DIRECT.
+fn direct_io_len_sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{DIRECT_IO_LEN_SIDECAR_EXTENSION}"));
+    PathBuf::from(name)
+}
+
+/// Probes the filesystem's preferred I/O block size via `statvfs`, falling back to
+/// `DEFAULT_BLOCK_SIZE` if the call fails or reports zero. Used to size and align the
+/// O_DIRECT write buffer.
+fn probe_block_size(dir: &Path) -> usize {
+    rustix::fs::statvfs(dir)
+        .ok()
+        .map(|stats| stats.f_bsize as usize)
+        .filter(|&bsize| bsize > 0)
+        .unwrap_or(DEFAULT_BLOCK_SIZE)
+}
+
+/// Heap buffer aligned to `align`, required because O_DIRECT rejects writes from
+/// misaligned buffer addresses, not just misaligned file offsets/lengths.
+struct AlignedBuffer {
+    ptr: std::ptr::NonNull<u8>,
+    len: usize,
+    layout: std::alloc::Layout,
+}
+
+// Safe to send: the buffer owns its allocation outright and is only ever touched by
+// one task at a time (built on the async side, written from inside `spawn_blocking`).
+unsafe impl Send for AlignedBuffer {}
+
+impl AlignedBuffer {
+    fn zeroed(len: usize, align: usize) -> Self {
+        let layout = std::alloc::Layout::from_size_align(len, align)
+            .expect("O_DIRECT buffer length is always a multiple of its alignment");
+        let raw = unsafe { std::alloc::alloc_zeroed(layout) };
+        let ptr = std::ptr::NonNull::new(raw).unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+        Self { ptr, len, layout }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { std::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+/// Writes `data` to `path` with O_DIRECT, bypassing the page cache: `data` is copied
+/// into a buffer aligned to (and padded with zeros up to a multiple of) the device's
+/// logical block size, since O_DIRECT requires the write to be block-aligned on both
+/// ends. The real length is recorded in a `.len` sidecar so `load_batch` can trim the
+/// padding back off.
+///
+/// Returns `Err` only for genuine I/O failures; a filesystem that doesn't support
+/// O_DIRECT at all (some overlay/network filesystems return `EINVAL` or `ENOTSUP` from
+/// `open`) is reported as `Ok(false)` so the caller can fall back to a buffered write.
+fn try_write_direct_io(path: &Path, data: &[u8]) -> Result<bool, DlqError> {
+    use rustix::fs::{Mode, OFlags};
+    use rustix::io::Errno;
+
+    let block_size = probe_block_size(path.parent().unwrap_or(path));
+    let aligned_len = data.len().div_ceil(block_size) * block_size;
+
+    let mut buffer = AlignedBuffer::zeroed(aligned_len, block_size);
+    buffer.as_mut_slice()[..data.len()].copy_from_slice(data);
+
+    let fd = match rustix::fs::open(
+        path,
+        OFlags::WRONLY | OFlags::CREATE | OFlags::TRUNC | OFlags::DIRECT,
+        Mode::from_bits_truncate(0o644),
+    ) {
+        Ok(fd) => fd,
+        Err(Errno::INVAL) | Err(Errno::OPNOTSUPP) | Err(Errno::NOTSUP) => return Ok(false),
+        Err(e) => return Err(DlqError::Io(e.into())),
+    };
+
+    let file = std::fs::File::from(fd);
+    {
+        use std::io::Write;
+        (&file).write_all(buffer.as_slice())?;
+    }
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::write(direct_io_len_sidecar_path(path), data.len().to_string())?;
+
+    Ok(true)
+}
+
+/// Writes `data` as a batch file's contents, using O_DIRECT when `direct_io` is set and
+/// the filesystem accepts it, and a plain buffered write otherwise. Returns whether the
+/// O_DIRECT path was actually used, so the caller knows whether a `.len` sidecar needs
+/// to be carried along through any subsequent rename.
+async fn write_batch_contents(path: &Path, data: &[u8], direct_io: bool) -> Result<bool, DlqError> {
+    if direct_io {
+        let write_path = path.to_path_buf();
+        let owned_data = data.to_vec();
+        let wrote_direct = tokio::task::spawn_blocking(move || try_write_direct_io(&write_path, &owned_data))
+            .await
+            .map_err(|e| DlqError::Io(std::io::Error::other(e)))??;
+
+        if wrote_direct {
+            return Ok(true);
+        }
+
+        warn!(
+            path = %path.display(),
+            "O_DIRECT write rejected by filesystem, falling back to buffered write"
+        );
+    }
+
+    fs::write(path, data).await?;
+    Ok(false)
+}
+
+/// Serializes and compresses `records` exactly like `persist_batch`, but uploads the
+/// result to `bucket` in `object_store` instead of writing the full payload to local
+/// disk. A small `RemoteStub` pointer is written locally in its place (in the same
+/// table directory, sorting alongside local batches by timestamp) so `list_batches` and
+/// replay can find it without a network round trip.
+pub async fn persist_batch_remote<T>(
+    records: &[T],
+    table_name: String,
+    base_path: &Path,
+    bucket: &str,
+    object_store: &dyn ObjectStore,
 ) -> Result<PathBuf, DlqError>
 where
     T: Serialize + Clone,
@@ -46,64 +399,365 @@ where
         )));
     }
 
-    // Create table-specific directory
     let table_dir = base_path.join(&table_name);
     fs::create_dir_all(&table_dir).await?;
 
-    // Generate unique filename with timestamp and UUID
     let timestamp = Utc::now();
     let batch_id = uuid::Uuid::new_v4();
-    let filename = format!(
-        "{}_{}.json",
+    let key = format!(
+        "{}/{}_{}.{}",
+        table_name,
         timestamp.format("%Y-%m-%dT%H-%M-%S%.3f"),
-        batch_id
+        batch_id,
+        COMPRESSED_EXTENSION
     );
-    let file_path = table_dir.join(&filename);
 
-    // Create batch envelope
     let envelope = BatchEnvelope {
         table_name: table_name.clone(),
         timestamp,
         record_count: records.len(),
         records: records.to_vec(),
+        retry_count: 0,
+        checksum: Some(compute_checksum(records)?),
     };
-
-    // Serialize to JSON
     let json_data = serde_json::to_vec(&envelope)?;
+    let compressed = zstd::stream::encode_all(json_data.as_slice(), ZSTD_COMPRESSION_LEVEL)
+        .map_err(DlqError::Io)?;
 
-    // Atomic write: write to temp file, then rename
-    let temp_path = table_dir.join(format!("{}.tmp", filename));
-    fs::write(&temp_path, &json_data).await?;
-    fs::rename(&temp_path, &file_path).await?;
+    object_store.put(bucket, &key, compressed).await?;
+
+    let stub = RemoteStub {
+        table_name: table_name.clone(),
+        timestamp,
+        record_count: records.len(),
+        bucket: bucket.to_string(),
+        key: key.clone(),
+    };
+    let stub_filename = format!(
+        "{}_{}.{}",
+        timestamp.format("%Y-%m-%dT%H-%M-%S%.3f"),
+        batch_id,
+        REMOTE_STUB_EXTENSION
+    );
+    let stub_path = table_dir.join(&stub_filename);
+    let stub_data = serde_json::to_vec(&stub)?;
+    let temp_path = table_dir.join(format!("{}.tmp", stub_filename));
+    fs::write(&temp_path, &stub_data).await?;
+    fs::rename(&temp_path, &stub_path).await?;
+    fsync_path(&stub_path).await?;
+    fsync_path(&table_dir).await?;
+
+    // `size_bytes` is the local stub's size, not the uploaded payload's, so it stays
+    // consistent with `calculate_disk_usage`, which only ever sees local bytes.
+    write_manifest(
+        &stub_path,
+        &BatchManifest {
+            created_at: timestamp,
+            size_bytes: stub_data.len() as u64,
+            row_count: records.len(),
+            source_table: table_name.clone(),
+            retry_count: 0,
+        },
+    )
+    .await?;
 
     info!(
         table = %table_name,
         records = records.len(),
-        path = %file_path.display(),
-        "Batch persisted to DLQ"
+        bucket,
+        key,
+        "Batch spilled to remote DLQ tier"
     );
 
+    Ok(stub_path)
+}
+
+/// Loads a remote stub pointer from disk. Does not fetch the underlying payload; use
+/// the stub's `bucket`/`key` with an `ObjectStore` for that.
+pub async fn load_remote_stub(path: &Path) -> Result<RemoteStub, DlqError> {
+    let raw = fs::read(path).await?;
+    Ok(serde_json::from_slice(&raw)?)
+}
+
+/// Whether a path is a remote stub pointer (`.remote.json`) rather than a full local
+/// batch file.
+pub fn is_remote_stub(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|name| name.ends_with(&format!(".{REMOTE_STUB_EXTENSION}")))
+        .unwrap_or(false)
+}
+
+/// Classifies a path returned by `list_batches` as `Local` or `Remote`, loading the stub
+/// in the `Remote` case so callers have the bucket/key without a second disk read.
+pub async fn spill_location_for(path: &Path) -> Result<SpillLocation, DlqError> {
+    if is_remote_stub(path) {
+        let stub = load_remote_stub(path).await?;
+        Ok(SpillLocation::Remote {
+            stub_path: path.to_path_buf(),
+            bucket: stub.bucket,
+            key: stub.key,
+        })
+    } else {
+        Ok(SpillLocation::Local(path.to_path_buf()))
+    }
+}
+
+/// Fetches a remote-spilled batch's full envelope from object storage via its stub,
+/// transparently decompressing it the same way a local `.json.zst` file would be.
+pub async fn load_remote_batch<T>(
+    stub: &RemoteStub,
+    object_store: &dyn ObjectStore,
+) -> Result<BatchEnvelope<T>, DlqError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let bytes = object_store.get(&stub.bucket, &stub.key).await?;
+    let json_data = zstd::stream::decode_all(bytes.as_slice()).map_err(DlqError::Io)?;
+    Ok(serde_json::from_slice(&json_data)?)
+}
+
+/// Writes an already-built `BatchEnvelope` to local disk, preserving its `retry_count`
+/// rather than resetting it like `persist_batch` does. Used to drain a batch back from
+/// the remote tier to local disk once local capacity has recovered, without losing the
+/// replay attempts it already burned while on the remote tier (see `dlq::replay`).
+pub async fn persist_envelope_locally<T>(
+    envelope: &BatchEnvelope<T>,
+    base_path: &Path,
+) -> Result<PathBuf, DlqError>
+where
+    T: Serialize,
+{
+    let table_dir = base_path.join(&envelope.table_name);
+    fs::create_dir_all(&table_dir).await?;
+
+    let batch_id = uuid::Uuid::new_v4();
+    let filename = format!(
+        "{}_{}.{}",
+        envelope.timestamp.format("%Y-%m-%dT%H-%M-%S%.3f"),
+        batch_id,
+        COMPRESSED_EXTENSION
+    );
+    let file_path = table_dir.join(&filename);
+
+    let json_data = serde_json::to_vec(envelope)?;
+    let compressed = zstd::stream::encode_all(json_data.as_slice(), ZSTD_COMPRESSION_LEVEL)
+        .map_err(DlqError::Io)?;
+
+    let temp_path = table_dir.join(format!("{}.tmp", filename));
+    fs::write(&temp_path, &compressed).await?;
+    fs::rename(&temp_path, &file_path).await?;
+    fsync_path(&file_path).await?;
+    fsync_path(&table_dir).await?;
+
+    write_manifest(
+        &file_path,
+        &BatchManifest {
+            created_at: envelope.timestamp,
+            size_bytes: compressed.len() as u64,
+            row_count: envelope.record_count,
+            source_table: envelope.table_name.clone(),
+            retry_count: envelope.retry_count,
+        },
+    )
+    .await?;
+
     Ok(file_path)
 }
 
-/// Loads a batch from disk
+/// Counts how many of a table's pending batches have their payload on local disk versus
+/// spilled to the remote tier, for `DlqHealthStats`.
+pub async fn count_local_and_remote_batches(
+    base_path: &Path,
+    table_name: &str,
+) -> Result<(usize, usize), DlqError> {
+    let batches = list_batches(base_path, table_name).await?;
+    let remote = batches.iter().filter(|p| is_remote_stub(p)).count();
+    let local = batches.len() - remote;
+    Ok((local, remote))
+}
+
+/// Loads a batch from disk, transparently decompressing `.json.zst` files. Plain
+/// `.json` files written before compression was introduced are still read as-is. If a
+/// `.len` sidecar is present (the batch was persisted with `DLQ_DIRECT_IO`), the raw
+/// bytes are trimmed to its recorded length first to strip the O_DIRECT alignment pad.
+///
+/// If the envelope carries a `checksum` (absent only for batches persisted before this
+/// field existed), it's verified against the freshly-deserialized `records` and mismatch
+/// is reported as `DlqError::ChecksumMismatch`, catching silent truncation or bit-rot
+/// that a malformed-but-still-parseable JSON document wouldn't otherwise surface.
 pub async fn load_batch<T>(path: &Path) -> Result<BatchEnvelope<T>, DlqError>
 where
-    T: serde::de::DeserializeOwned,
+    T: serde::de::DeserializeOwned + Serialize,
 {
-    let json_data = fs::read(path).await?;
-    let envelope = serde_json::from_slice(&json_data)?;
+    let mut raw = fs::read(path).await?;
+
+    if let Some(logical_len) = read_direct_io_sidecar_len(path).await? {
+        raw.truncate(logical_len);
+    }
+
+    let json_data = if is_compressed(path) {
+        zstd::stream::decode_all(raw.as_slice()).map_err(DlqError::Io)?
+    } else {
+        raw
+    };
+
+    let envelope: BatchEnvelope<T> = serde_json::from_slice(&json_data)?;
+
+    if let Some(expected) = envelope.checksum {
+        let actual = compute_checksum(&envelope.records)?;
+        if actual != expected {
+            return Err(DlqError::ChecksumMismatch { expected, actual });
+        }
+    }
+
     Ok(envelope)
 }
 
+/// Reads just the header fields of a batch (table name, timestamp, record count, retry
+/// count) without materializing its `records`, for callers that only need to decide
+/// whether to act on a batch (see `dlq::scheduler::BatchHandler::accept`). A remote
+/// stub's header fields are read directly off the stub; a local batch is still fully
+/// read and decompressed, since the records live in the same JSON document as the
+/// header, but `records` is deserialized into `()` and discarded rather than allocated.
+pub async fn peek_batch_meta(path: &Path) -> Result<BatchEnvelope<()>, DlqError> {
+    if is_remote_stub(path) {
+        let stub = load_remote_stub(path).await?;
+        return Ok(BatchEnvelope {
+            table_name: stub.table_name,
+            timestamp: stub.timestamp,
+            record_count: stub.record_count,
+            records: Vec::new(),
+            retry_count: 0,
+            checksum: None,
+        });
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Header {
+        table_name: String,
+        timestamp: chrono::DateTime<Utc>,
+        record_count: usize,
+        #[serde(default)]
+        retry_count: u32,
+    }
+
+    let mut raw = fs::read(path).await?;
+
+    if let Some(logical_len) = read_direct_io_sidecar_len(path).await? {
+        raw.truncate(logical_len);
+    }
+
+    let json_data = if is_compressed(path) {
+        zstd::stream::decode_all(raw.as_slice()).map_err(DlqError::Io)?
+    } else {
+        raw
+    };
+
+    let header: Header = serde_json::from_slice(&json_data)?;
+    Ok(BatchEnvelope {
+        table_name: header.table_name,
+        timestamp: header.timestamp,
+        record_count: header.record_count,
+        records: Vec::new(),
+        retry_count: header.retry_count,
+        checksum: None,
+    })
+}
+
+/// Reads `path`'s direct-I/O length sidecar if one exists, returning `None` for batches
+/// that were written with a plain buffered write (the common case).
+async fn read_direct_io_sidecar_len(path: &Path) -> Result<Option<usize>, DlqError> {
+    match fs::read_to_string(direct_io_len_sidecar_path(path)).await {
+        Ok(contents) => Ok(contents.trim().parse().ok()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(DlqError::Io(e)),
+    }
+}
+
+/// Whether a batch file path uses the compressed (`.json.zst`) extension.
+fn is_compressed(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|name| name.ends_with(".zst"))
+        .unwrap_or(false)
+}
+
+/// Whether a path looks like a batch file: compressed, the plain `.json` files written
+/// before compression was introduced, or a remote-stub pointer for a batch spilled to
+/// object storage. `list_batches` intentionally doesn't distinguish between these three
+/// at the filesystem-listing level; call `is_remote_stub` or `spill_location_for` on the
+/// result when the distinction matters.
+fn is_batch_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        // `.remote.json` stubs end in `.json` too, so this one check covers all three.
+        .map(|name| name.ends_with(".json") || name.ends_with(".json.zst"))
+        .unwrap_or(false)
+}
+
 /// Deletes a batch file from disk
 pub async fn delete_batch(path: &Path) -> Result<(), DlqError> {
     fs::remove_file(path).await?;
+    // Best-effort: most batches have no sidecar, so a missing-file error here is normal.
+    let _ = fs::remove_file(direct_io_len_sidecar_path(path)).await;
+    let _ = fs::remove_file(manifest_path(path)).await;
     debug!(path = %path.display(), "Batch deleted from DLQ");
     Ok(())
 }
 
-/// Lists all batch files for a given table, sorted by modification time (oldest first)
+/// Scans every table directory (plus `.metadata`) for `*.tmp` files left behind by a
+/// crash mid-write and removes them. A `.tmp` file's writer is gone by the time the
+/// process restarts, so it can never be completed into a real batch — it would
+/// otherwise sit on disk forever, slowly eating into the DLQ's disk quota.
+///
+/// Meant to be called once from `init_directories` on startup, before anything else
+/// touches the DLQ directories.
+pub async fn reap_orphaned_temp_files(base_path: &Path) -> Result<CleanupStats, DlqError> {
+    let mut stats = CleanupStats::default();
+
+    let mut scan_dirs: Vec<PathBuf> = crate::dlq::DLQ_TABLE_NAMES
+        .iter()
+        .map(|table| base_path.join(table))
+        .collect();
+    scan_dirs.push(base_path.join(".metadata"));
+
+    for dir in scan_dirs {
+        if !dir.exists() {
+            continue;
+        }
+
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let is_tmp = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|name| name.ends_with(".tmp"))
+                .unwrap_or(false);
+            if !is_tmp {
+                continue;
+            }
+
+            if let Err(e) = fs::remove_file(&path).await {
+                warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to remove orphaned DLQ temp file"
+                );
+                continue;
+            }
+            warn!(path = %path.display(), "Removed orphaned DLQ temp file left by a previous crash");
+            stats.deleted += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Lists all batch files for a given table, sorted oldest-first by each batch's real
+/// `created_at` (from its sidecar manifest, rebuilding it if missing/corrupt — see
+/// `load_or_rebuild_manifest`) rather than by filename.
 pub async fn list_batches(base_path: &Path, table_name: &str) -> Result<Vec<PathBuf>, DlqError> {
     let table_dir = base_path.join(table_name);
 
@@ -117,15 +771,39 @@ pub async fn list_batches(base_path: &Path, table_name: &str) -> Result<Vec<Path
 
     while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+        if is_batch_file(&path) {
             batches.push(path);
         }
     }
 
-    // Sort by file name (which includes timestamp)
-    batches.sort();
+    let mut batches_with_created_at = Vec::with_capacity(batches.len());
+    for path in batches {
+        let created_at = match load_or_rebuild_manifest(&path).await {
+            Ok(manifest) => manifest.created_at,
+            Err(e) => {
+                warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to load or rebuild DLQ batch manifest, listing it first rather than dropping it"
+                );
+                chrono::DateTime::<Utc>::from_timestamp(0, 0).unwrap_or_default()
+            }
+        };
+        batches_with_created_at.push((path, created_at));
+    }
+    batches_with_created_at.sort_by_key(|(_, created_at)| *created_at);
+
+    Ok(batches_with_created_at.into_iter().map(|(path, _)| path).collect())
+}
 
-    Ok(batches)
+/// Queries the free and total byte capacity of the filesystem backing `base_path`'s
+/// mount, via `statvfs`. Runs on a blocking thread since `statvfs` is a blocking
+/// syscall with no async equivalent in `tokio::fs`.
+fn statvfs_free_and_total_bytes(base_path: &Path) -> Result<(u64, u64), DlqError> {
+    let stats = rustix::fs::statvfs(base_path).map_err(std::io::Error::from)?;
+    let free_bytes = stats.f_bavail.saturating_mul(stats.f_frsize);
+    let total_bytes = stats.f_blocks.saturating_mul(stats.f_frsize);
+    Ok((free_bytes, total_bytes))
 }
 
 /// Calculates total disk usage of DLQ in bytes
@@ -169,6 +847,174 @@ pub async fn calculate_disk_usage(base_path: &Path) -> Result<u64, DlqError> {
     Ok(total_size)
 }
 
+/// Evicts the oldest batch files across all tables until total DLQ disk usage is back
+/// under `max_disk_bytes`, logging a warning with the number of batches dropped.
+///
+/// This is a soft, non-failing budget: unlike `check_disk_quota`, it never rejects an
+/// ingest failure. It runs *after* a batch has already been persisted, trading the
+/// oldest buffered data for headroom rather than blocking or erroring on new writes.
+///
+/// A remote-spilled batch's local stub counts toward candidates here like any other
+/// file; deleting it only drops the local pointer; it has no `ObjectStore` handle to
+/// also delete the uploaded payload, which is deferred to tiered-storage garbage
+/// collection rather than this in-process helper.
+///
+/// Candidates are ordered by each batch's manifest (see `load_or_rebuild_manifest`):
+/// batches that have already exhausted `max_replay_attempts` (if given) are evicted
+/// before any batch still eligible for replay, since they'll never successfully replay
+/// anyway; within that, real `created_at` breaks ties rather than a lexical filename
+/// sort. `size_bytes` also comes from the manifest, so eviction no longer costs an
+/// `fs::metadata` call per candidate.
+pub async fn evict_oldest_until_within_budget(
+    base_path: &Path,
+    max_disk_bytes: u64,
+    max_replay_attempts: Option<u32>,
+) -> Result<usize, DlqError> {
+    let mut current_bytes = calculate_disk_usage(base_path).await?;
+    if current_bytes <= max_disk_bytes {
+        return Ok(0);
+    }
+
+    let mut candidates = Vec::new();
+    for table_name in crate::dlq::DLQ_TABLE_NAMES {
+        for path in list_batches(base_path, table_name).await? {
+            let manifest = load_or_rebuild_manifest(&path).await?;
+            candidates.push((path, manifest));
+        }
+    }
+    candidates.sort_by_key(|(_, manifest)| {
+        let exhausted = max_replay_attempts
+            .map(|max| manifest.retry_count >= max)
+            .unwrap_or(false);
+        (!exhausted, manifest.created_at)
+    });
+
+    let mut evicted = 0usize;
+    for (path, manifest) in candidates {
+        if current_bytes <= max_disk_bytes {
+            break;
+        }
+        delete_batch(&path).await?;
+        current_bytes = current_bytes.saturating_sub(manifest.size_bytes);
+        evicted += 1;
+    }
+
+    if evicted > 0 {
+        warn!(
+            evicted,
+            remaining_bytes = current_bytes,
+            max_disk_bytes,
+            "Evicted oldest DLQ batches to stay within disk budget"
+        );
+    }
+
+    Ok(evicted)
+}
+
+/// Classification of a ClickHouse error for DLQ replay purposes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Connection resets, timeouts, and rate-limit/overload responses. Worth
+    /// retrying with the existing exponential backoff.
+    Transient,
+    /// Schema mismatches, malformed rows, type errors, and anything else that
+    /// will fail again on every retry. Should fail fast instead of looping forever.
+    Permanent,
+}
+
+/// Classifies a ClickHouse error as transient or permanent.
+///
+/// We match on the error's string representation rather than its variant because
+/// ClickHouse reports both connectivity problems and query-level rejections (including
+/// HTTP 429/503 rate-limit/overload responses) through the same response-parsing path.
+/// Anything not recognized as transient is treated as permanent so poison batches fail
+/// fast instead of blocking replay indefinitely.
+pub fn classify_clickhouse_error(error: &clickhouse::error::Error) -> ErrorClass {
+    const TRANSIENT_MARKERS: &[&str] = &[
+        "connection reset",
+        "connection refused",
+        "connection closed",
+        "broken pipe",
+        "timed out",
+        "timeout",
+        " 429",
+        " 503",
+        "too many simultaneous queries",
+        "overloaded",
+        "memory limit",
+    ];
+
+    let message = error.to_string().to_lowercase();
+    if TRANSIENT_MARKERS.iter().any(|marker| message.contains(marker)) {
+        ErrorClass::Transient
+    } else {
+        ErrorClass::Permanent
+    }
+}
+
+/// Moves a batch file into the `quarantine/` subdirectory under `base_path` instead of
+/// retrying or deleting it, preserving the bytes for offline inspection.
+pub async fn quarantine_batch(batch_path: &Path, base_path: &Path) -> Result<PathBuf, DlqError> {
+    let quarantine_dir = base_path.join("quarantine");
+    fs::create_dir_all(&quarantine_dir).await?;
+
+    let filename = batch_path.file_name().ok_or_else(|| {
+        DlqError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Batch path has no filename",
+        ))
+    })?;
+    let dest = quarantine_dir.join(filename);
+
+    fs::rename(batch_path, &dest).await?;
+    // Best-effort: most batches have no sidecar, so a missing-file error here is normal.
+    let _ = fs::rename(
+        direct_io_len_sidecar_path(batch_path),
+        direct_io_len_sidecar_path(&dest),
+    )
+    .await;
+    let _ = fs::rename(manifest_path(batch_path), manifest_path(&dest)).await;
+    BATCHES_QUARANTINED_TOTAL.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    warn!(
+        from = %batch_path.display(),
+        to = %dest.display(),
+        "Batch quarantined"
+    );
+
+    Ok(dest)
+}
+
+/// Overwrites a batch file in place with an updated envelope, e.g. after bumping its
+/// replay-attempt counter. Uses the same write-to-temp-then-rename pattern as `persist_batch`.
+pub async fn resave_batch<T>(path: &Path, envelope: &BatchEnvelope<T>) -> Result<(), DlqError>
+where
+    T: Serialize,
+{
+    let json_data = serde_json::to_vec(envelope)?;
+    // Keep the encoding `load_batch` expects from this path's extension.
+    let bytes = if is_compressed(path) {
+        zstd::stream::encode_all(json_data.as_slice(), ZSTD_COMPRESSION_LEVEL).map_err(DlqError::Io)?
+    } else {
+        json_data
+    };
+    let temp_path = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&temp_path, &bytes).await?;
+    fs::rename(&temp_path, path).await?;
+    // The rewrite is a plain buffered write, so a direct-I/O length sidecar from the
+    // original persist no longer describes this file.
+    let _ = fs::remove_file(direct_io_len_sidecar_path(path)).await;
+
+    // Keep the manifest's `retry_count` in sync so quota eviction's exhausted-retry
+    // preference (see `evict_oldest_until_within_budget`) reflects this batch's latest
+    // attempt count instead of whatever it was at persist time.
+    let mut manifest = load_or_rebuild_manifest(path).await?;
+    manifest.retry_count = envelope.retry_count;
+    manifest.size_bytes = bytes.len() as u64;
+    write_manifest(path, &manifest).await?;
+
+    Ok(())
+}
+
 /// Checks if disk quota would be exceeded by writing a batch
 ///
 /// # Soft Limit Enforcement
@@ -182,13 +1028,25 @@ pub async fn calculate_disk_usage(base_path: &Path) -> Result<u64, DlqError> {
 /// This is **acceptable** for the DLQ use case because:
 /// 1. The DLQ is an emergency buffer during ClickHouse outages (rare occurrence)
 /// 2. Conservative 1KB/record estimate provides built-in headroom
-/// 3. Background cleanup enforces quota retroactively (see `dlq::cleanup::enforce_disk_quota`)
+/// 3. The scheduler's `QuotaEnforcementHandler` enforces quota retroactively (see
+///    `dlq::scheduler`)
 /// 4. Strict enforcement would require locks that could slow down critical failure paths
 ///
 /// The quota acts as a safety net to prevent unbounded growth, not a hard invariant.
+///
+/// # Reserved Disk Ratio
+///
+/// `max_disk_mb` only bounds the DLQ's own usage, which is dangerous when `base_path`
+/// shares a volume with ClickHouse data, logs, or other tenants: the device can fill up
+/// even though the DLQ is well under its own cap. `reserved_disk_ratio` adds an
+/// independent check against the filesystem itself (via `statvfs`), rejecting the write
+/// if it would leave less than `reserved_disk_ratio * total_bytes` free on the mount.
+/// A ratio of `0.0` disables this check. Both limits are enforced independently; the
+/// write fails if either trips.
 pub async fn check_disk_quota(
     base_path: &Path,
     max_disk_mb: u64,
+    reserved_disk_ratio: f64,
     batch_size_estimate: usize,
 ) -> Result<(), DlqError> {
     let current_bytes = calculate_disk_usage(base_path).await?;
@@ -210,6 +1068,28 @@ pub async fn check_disk_quota(
         return Err(DlqError::DiskQuotaExceeded);
     }
 
+    if reserved_disk_ratio > 0.0 {
+        let path = base_path.to_path_buf();
+        let (free_bytes, total_bytes) = tokio::task::spawn_blocking(move || {
+            statvfs_free_and_total_bytes(&path)
+        })
+        .await
+        .map_err(|e| DlqError::Io(std::io::Error::other(e)))??;
+
+        let reserved_bytes = (total_bytes as f64 * reserved_disk_ratio) as u64;
+        let free_after_write = free_bytes.saturating_sub(estimated_new_bytes as u64);
+
+        if total_bytes > 0 && free_after_write < reserved_bytes {
+            warn!(
+                free_bytes,
+                total_bytes,
+                reserved_disk_ratio,
+                "DLQ write would push filesystem free space below the reserved ratio"
+            );
+            return Err(DlqError::DiskQuotaExceeded);
+        }
+    }
+
     Ok(())
 }
 
@@ -218,6 +1098,40 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[tokio::test]
+    async fn test_quarantine_batch_moves_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let path = persist_batch(&vec![1, 2, 3], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let quarantined_path = quarantine_batch(&path, base_path).await.unwrap();
+
+        assert!(!path.exists());
+        assert!(quarantined_path.exists());
+        assert_eq!(quarantined_path.parent().unwrap(), base_path.join("quarantine"));
+    }
+
+    #[tokio::test]
+    async fn test_resave_batch_persists_retry_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let path = persist_batch(&vec![1, 2, 3], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let mut envelope: BatchEnvelope<i32> = load_batch(&path).await.unwrap();
+        envelope.retry_count += 1;
+        resave_batch(&path, &envelope).await.unwrap();
+
+        let reloaded: BatchEnvelope<i32> = load_batch(&path).await.unwrap();
+        assert_eq!(reloaded.retry_count, 1);
+        assert_eq!(reloaded.records, vec![1, 2, 3]);
+    }
+
     #[tokio::test]
     async fn test_persist_and_load_batch() {
         let temp_dir = TempDir::new().unwrap();
@@ -227,7 +1141,7 @@ mod tests {
         let table_name = "test_table".to_string();
 
         // Persist batch
-        let path = persist_batch(&records, table_name.clone(), base_path)
+        let path = persist_batch(&records, table_name.clone(), base_path, false)
             .await
             .unwrap();
 
@@ -247,7 +1161,7 @@ mod tests {
         let base_path = temp_dir.path();
 
         let records = vec!["a", "b", "c"];
-        let path = persist_batch(&records, "test_table".to_string(), base_path)
+        let path = persist_batch(&records, "test_table".to_string(), base_path, false)
             .await
             .unwrap();
 
@@ -267,7 +1181,7 @@ mod tests {
         // Create multiple batches
         for i in 0..3 {
             tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-            persist_batch(&vec![i], table_name.clone(), base_path)
+            persist_batch(&vec![i], table_name.clone(), base_path, false)
                 .await
                 .unwrap();
         }
@@ -287,7 +1201,7 @@ mod tests {
 
         // Persist a batch
         let records = vec![1, 2, 3, 4, 5];
-        persist_batch(&records, "test_table".to_string(), base_path)
+        persist_batch(&records, "test_table".to_string(), base_path, false)
             .await
             .unwrap();
 
@@ -305,19 +1219,428 @@ mod tests {
         let max_disk_mb = 1;
 
         // Try to check quota for very large batch (2000 records ~= 2MB)
-        let result = check_disk_quota(base_path, max_disk_mb, 2000).await;
+        let result = check_disk_quota(base_path, max_disk_mb, 0.0, 2000).await;
+
+        assert!(matches!(result, Err(DlqError::DiskQuotaExceeded)));
+    }
+
+    #[tokio::test]
+    async fn test_check_disk_quota_reserved_ratio_above_one_always_trips() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        // A reserved ratio above 1.0 can never be satisfied, so this must trip
+        // regardless of how much of the underlying device is actually free.
+        let result = check_disk_quota(base_path, 1024, 1.1, 1).await;
 
         assert!(matches!(result, Err(DlqError::DiskQuotaExceeded)));
     }
 
+    #[tokio::test]
+    async fn test_check_disk_quota_zero_ratio_skips_filesystem_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        // A zero ratio disables the statvfs-based check entirely; only the existing
+        // max_disk_mb ceiling applies.
+        let result = check_disk_quota(base_path, 1024, 0.0, 1).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_load_batch_reads_legacy_uncompressed_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let table_dir = temp_dir.path().join("test_table");
+        fs::create_dir_all(&table_dir).await.unwrap();
+
+        let envelope = BatchEnvelope {
+            table_name: "test_table".to_string(),
+            timestamp: Utc::now(),
+            record_count: 2,
+            records: vec![1, 2],
+            retry_count: 0,
+            checksum: None,
+        };
+        let path = table_dir.join("2024-01-01T00-00-00.000_legacy.json");
+        fs::write(&path, serde_json::to_vec(&envelope).unwrap())
+            .await
+            .unwrap();
+
+        let loaded: BatchEnvelope<i32> = load_batch(&path).await.unwrap();
+        assert_eq!(loaded.records, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_list_batches_includes_legacy_and_compressed_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+        let table_dir = base_path.join("test_table");
+        fs::create_dir_all(&table_dir).await.unwrap();
+
+        let envelope = BatchEnvelope {
+            table_name: "test_table".to_string(),
+            timestamp: Utc::now(),
+            record_count: 1,
+            records: vec![1],
+            retry_count: 0,
+            checksum: None,
+        };
+        fs::write(
+            table_dir.join("2024-01-01T00-00-00.000_legacy.json"),
+            serde_json::to_vec(&envelope).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        persist_batch(&vec![2], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let batches = list_batches(base_path, "test_table").await.unwrap();
+        assert_eq!(batches.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_evict_oldest_until_within_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        for i in 0..5 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            persist_batch(&vec![i; 100], "test_table".to_string(), base_path, false)
+                .await
+                .unwrap();
+        }
+
+        let usage_before = calculate_disk_usage(base_path).await.unwrap();
+        let budget = usage_before / 2;
+
+        let evicted = evict_oldest_until_within_budget(base_path, budget, None)
+            .await
+            .unwrap();
+
+        assert!(evicted > 0);
+        let usage_after = calculate_disk_usage(base_path).await.unwrap();
+        assert!(usage_after <= budget || evicted == 5);
+    }
+
+    #[tokio::test]
+    async fn test_evict_noop_when_within_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        persist_batch(&vec![1, 2, 3], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let evicted = evict_oldest_until_within_budget(base_path, u64::MAX, None)
+            .await
+            .unwrap();
+
+        assert_eq!(evicted, 0);
+        assert_eq!(list_batches(base_path, "test_table").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_evict_prefers_batches_with_exhausted_retry_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        // Persist oldest-first: a healthy batch, then a newer one that has already
+        // exhausted its replay budget.
+        let healthy_path = persist_batch(&vec![1; 100], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        let exhausted_path = persist_batch(&vec![2; 100], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let mut envelope = load_batch::<i32>(&exhausted_path).await.unwrap();
+        envelope.retry_count = 5;
+        resave_batch(&exhausted_path, &envelope).await.unwrap();
+
+        let usage_before = calculate_disk_usage(base_path).await.unwrap();
+        // Tight enough budget to evict exactly one batch.
+        let budget = usage_before - 1;
+
+        let evicted = evict_oldest_until_within_budget(base_path, budget, Some(3))
+            .await
+            .unwrap();
+
+        assert_eq!(evicted, 1);
+        let remaining = list_batches(base_path, "test_table").await.unwrap();
+        assert_eq!(remaining, vec![healthy_path]);
+        assert_ne!(remaining, vec![exhausted_path]);
+    }
+
+    #[tokio::test]
+    async fn test_load_or_rebuild_manifest_recovers_missing_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let path = persist_batch(&vec![1, 2, 3], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        // Simulate a crash between writing the batch and writing its manifest.
+        fs::remove_file(manifest_path(&path)).await.unwrap();
+
+        let manifest = load_or_rebuild_manifest(&path).await.unwrap();
+        assert_eq!(manifest.source_table, "test_table");
+        assert_eq!(manifest.row_count, 3);
+
+        // The rebuild should have re-persisted the manifest so the next read is a
+        // straight hit rather than rebuilding again.
+        assert!(read_manifest(&path).await.is_some());
+    }
+
     #[tokio::test]
     async fn test_persist_empty_batch_fails() {
         let temp_dir = TempDir::new().unwrap();
         let base_path = temp_dir.path();
 
         let records: Vec<i32> = vec![];
-        let result = persist_batch(&records, "test_table".to_string(), base_path).await;
+        let result = persist_batch(&records, "test_table".to_string(), base_path, false).await;
 
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_persist_batch_remote_uploads_and_leaves_local_stub() {
+        use crate::dlq::object_store::InMemoryObjectStore;
+
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+        let store = InMemoryObjectStore::new();
+
+        let stub_path = persist_batch_remote(
+            &vec![1, 2, 3],
+            "test_table".to_string(),
+            base_path,
+            "test-bucket",
+            &store,
+        )
+        .await
+        .unwrap();
+
+        assert!(is_remote_stub(&stub_path));
+        let stub = load_remote_stub(&stub_path).await.unwrap();
+        assert_eq!(stub.bucket, "test-bucket");
+        assert_eq!(stub.record_count, 3);
+
+        let uploaded = store.get(&stub.bucket, &stub.key).await.unwrap();
+        let decompressed = zstd::stream::decode_all(uploaded.as_slice()).unwrap();
+        let envelope: BatchEnvelope<i32> = serde_json::from_slice(&decompressed).unwrap();
+        assert_eq!(envelope.records, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_list_batches_includes_remote_stubs() {
+        use crate::dlq::object_store::InMemoryObjectStore;
+
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+        let store = InMemoryObjectStore::new();
+
+        persist_batch(&vec![1], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+        persist_batch_remote(&vec![2], "test_table".to_string(), base_path, "bucket", &store)
+            .await
+            .unwrap();
+
+        let batches = list_batches(base_path, "test_table").await.unwrap();
+        assert_eq!(batches.len(), 2);
+
+        let (local, remote) = count_local_and_remote_batches(base_path, "test_table")
+            .await
+            .unwrap();
+        assert_eq!(local, 1);
+        assert_eq!(remote, 1);
+    }
+
+    #[tokio::test]
+    async fn test_spill_location_for_distinguishes_local_and_remote() {
+        use crate::dlq::object_store::InMemoryObjectStore;
+        use crate::dlq::types::SpillLocation;
+
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+        let store = InMemoryObjectStore::new();
+
+        let local_path = persist_batch(&vec![1], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+        let stub_path =
+            persist_batch_remote(&vec![2], "test_table".to_string(), base_path, "bucket", &store)
+                .await
+                .unwrap();
+
+        assert!(matches!(
+            spill_location_for(&local_path).await.unwrap(),
+            SpillLocation::Local(_)
+        ));
+        assert!(matches!(
+            spill_location_for(&stub_path).await.unwrap(),
+            SpillLocation::Remote { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_remote_batch_round_trips_through_object_store() {
+        use crate::dlq::object_store::InMemoryObjectStore;
+
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+        let store = InMemoryObjectStore::new();
+
+        let stub_path =
+            persist_batch_remote(&vec![1, 2, 3], "test_table".to_string(), base_path, "bucket", &store)
+                .await
+                .unwrap();
+        let stub = load_remote_stub(&stub_path).await.unwrap();
+
+        let envelope: BatchEnvelope<i32> = load_remote_batch(&stub, &store).await.unwrap();
+        assert_eq!(envelope.records, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_persist_envelope_locally_preserves_retry_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let envelope = BatchEnvelope {
+            table_name: "test_table".to_string(),
+            timestamp: Utc::now(),
+            record_count: 2,
+            records: vec![1, 2],
+            retry_count: 3,
+            checksum: Some(compute_checksum(&[1, 2]).unwrap()),
+        };
+
+        let path = persist_envelope_locally(&envelope, base_path).await.unwrap();
+        let reloaded: BatchEnvelope<i32> = load_batch(&path).await.unwrap();
+        assert_eq!(reloaded.retry_count, 3);
+        assert_eq!(reloaded.records, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_load_batch_detects_checksum_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let path = persist_batch(&vec![1, 2, 3], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let mut envelope: BatchEnvelope<i32> = load_batch(&path).await.unwrap();
+        envelope.records.push(4); // corrupt the payload without touching the checksum
+        resave_batch(&path, &envelope).await.unwrap();
+
+        let result = load_batch::<i32>(&path).await;
+        assert!(matches!(result, Err(DlqError::ChecksumMismatch { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_load_batch_skips_verification_when_checksum_absent() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+        let table_dir = base_path.join("test_table");
+        fs::create_dir_all(&table_dir).await.unwrap();
+
+        // A legacy envelope with no checksum field should load fine, even though its
+        // `records` wouldn't match any checksum we'd compute for it.
+        let envelope = BatchEnvelope {
+            table_name: "test_table".to_string(),
+            timestamp: Utc::now(),
+            record_count: 1,
+            records: vec![1],
+            retry_count: 0,
+            checksum: None,
+        };
+        let path = table_dir.join("2024-01-01T00-00-00.000_legacy.json");
+        fs::write(&path, serde_json::to_vec(&envelope).unwrap())
+            .await
+            .unwrap();
+
+        let loaded: BatchEnvelope<i32> = load_batch(&path).await.unwrap();
+        assert_eq!(loaded.records, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_quarantine_batch_increments_counter() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let path = persist_batch(&vec![1], "test_table".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let before = batches_quarantined_total();
+        quarantine_batch(&path, base_path).await.unwrap();
+        assert_eq!(batches_quarantined_total(), before + 1);
+    }
+
+    #[tokio::test]
+    async fn test_persist_batch_direct_io_round_trips() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        // Many sandboxes/CI filesystems (overlayfs, tmpfs) reject O_DIRECT, in which case
+        // `write_batch_contents` falls back to a buffered write; either way the batch
+        // must still load back byte-for-byte.
+        let records = vec![1, 2, 3, 4, 5];
+        let path = persist_batch(&records, "test_table".to_string(), base_path, true)
+            .await
+            .unwrap();
+
+        let loaded: BatchEnvelope<i32> = load_batch(&path).await.unwrap();
+        assert_eq!(loaded.records, records);
+    }
+
+    #[tokio::test]
+    async fn test_reap_orphaned_temp_files_removes_stale_tmp_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let table_dir = base_path.join("mlop_metrics");
+        fs::create_dir_all(&table_dir).await.unwrap();
+        let orphaned_tmp = table_dir.join("2024-01-01T00-00-00.000_crashed.json.zst.tmp");
+        fs::write(&orphaned_tmp, b"partial write").await.unwrap();
+
+        let metadata_dir = base_path.join(".metadata");
+        fs::create_dir_all(&metadata_dir).await.unwrap();
+        let orphaned_metadata_tmp = metadata_dir.join("stale.tmp");
+        fs::write(&orphaned_metadata_tmp, b"partial").await.unwrap();
+
+        // A real batch file should survive the reap untouched.
+        let real_batch = persist_batch(&vec![1, 2, 3], "mlop_metrics".to_string(), base_path, false)
+            .await
+            .unwrap();
+
+        let stats = reap_orphaned_temp_files(base_path).await.unwrap();
+
+        assert_eq!(stats.deleted, 2);
+        assert!(!orphaned_tmp.exists());
+        assert!(!orphaned_metadata_tmp.exists());
+        assert!(real_batch.exists());
+    }
+
+    #[tokio::test]
+    async fn test_direct_io_sidecar_is_cleaned_up_on_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path();
+
+        let path = persist_batch(&vec![1, 2, 3], "test_table".to_string(), base_path, true)
+            .await
+            .unwrap();
+        let sidecar = direct_io_len_sidecar_path(&path);
+
+        delete_batch(&path).await.unwrap();
+
+        assert!(!path.exists());
+        assert!(!sidecar.exists());
+    }
 }
@@ -12,6 +12,36 @@ pub struct BatchEnvelope<T> {
     pub record_count: usize,
     /// The actual records
     pub records: Vec<T>,
+    /// Number of times replay has been attempted and failed for this batch.
+    /// Defaults to 0 so envelopes persisted before this field existed still load.
+    #[serde(default)]
+    pub retry_count: u32,
+    /// CRC32 of `records`' serialized bytes, computed at persist time and verified by
+    /// `storage::load_batch` to catch truncation or bit-rot before it reaches replay.
+    /// `None` for envelopes persisted before this field existed, which skip
+    /// verification rather than being treated as corrupt.
+    #[serde(default)]
+    pub checksum: Option<u32>,
+}
+
+/// Sidecar record written alongside every batch file, capturing the fields
+/// `storage::list_batches`/`evict_oldest_until_within_budget` need to order and triage
+/// batches without re-deriving them from the filename or re-reading the batch payload.
+/// Written at persist time and kept up to date by anything that mutates the batch (e.g.
+/// `storage::resave_batch` bumping `retry_count`); `storage::load_or_rebuild_manifest`
+/// reconstructs it from the batch file itself if it's ever missing or corrupt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchManifest {
+    /// When the batch was originally persisted.
+    pub created_at: DateTime<Utc>,
+    /// Size in bytes of the batch file on disk at the time the manifest was (re)written.
+    pub size_bytes: u64,
+    /// Number of records in the batch.
+    pub row_count: usize,
+    /// Table the batch belongs to.
+    pub source_table: String,
+    /// Number of times replay has failed for this batch so far.
+    pub retry_count: u32,
 }
 
 /// Statistics about DLQ operations
@@ -29,6 +59,12 @@ pub struct DlqStats {
     pub disk_usage_mb: u64,
     /// Age of oldest batch in hours
     pub oldest_batch_age_hours: f64,
+    /// Lifetime count of batches moved to quarantine (checksum mismatch, corrupt JSON,
+    /// or exhausted replay attempts). Mirrors `storage::batches_quarantined_total`.
+    pub batches_quarantined_total: u64,
+    /// Lifetime count of batches archived to object storage instead of hard-deleted.
+    /// Mirrors `archive::batches_archived_total`.
+    pub batches_archived_total: u64,
 }
 
 /// Statistics returned by DLQ health endpoint
@@ -41,6 +77,49 @@ pub struct DlqHealthStats {
     pub records_pending: u64,
     /// Disk usage in MB
     pub disk_usage_mb: u64,
+    /// Of `batches_pending`, how many have their full payload on local disk
+    pub batches_pending_local: u64,
+    /// Of `batches_pending`, how many have spilled to object storage and are
+    /// represented locally only by a small stub pointer
+    pub batches_pending_remote: u64,
+    /// Lifetime count of batches moved to quarantine (checksum mismatch, corrupt JSON,
+    /// or exhausted replay attempts) since process start
+    pub batches_quarantined_total: u64,
+    /// Lifetime count of batches archived to object storage (rather than hard-deleted)
+    /// by TTL cleanup or quota enforcement since process start. Mirrors
+    /// `archive::batches_archived_total`; stays zero when `DlqConfig::archive_mode` is
+    /// `HardDelete`.
+    pub batches_archived_total: u64,
+}
+
+/// Where a persisted batch's full payload actually lives. Every batch starts out
+/// `Local`; once on-disk usage crosses `DlqConfig::remote_spill_high_water_ratio`, new
+/// batches spill to object storage instead and are recorded as `Remote` so replay knows
+/// to fetch them from there. TTL cleanup and quota eviction remove only the local stub;
+/// the remote payload is not deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpillLocation {
+    /// Full `BatchEnvelope` payload is the file at this path.
+    Local(std::path::PathBuf),
+    /// Full `BatchEnvelope` payload lives in object storage; `stub_path` is the small
+    /// local pointer file recording where.
+    Remote {
+        stub_path: std::path::PathBuf,
+        bucket: String,
+        key: String,
+    },
+}
+
+/// The small stub persisted to disk in place of a full batch once its payload has been
+/// uploaded to object storage, so `list_batches` and replay can find it without
+/// fetching the payload up front.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteStub {
+    pub table_name: String,
+    pub timestamp: DateTime<Utc>,
+    pub record_count: usize,
+    pub bucket: String,
+    pub key: String,
 }
 
 /// Statistics from a replay operation
@@ -52,6 +131,8 @@ pub struct ReplayStats {
     pub failed_batches: usize,
     /// Number of records in batches that failed to replay
     pub failed_records: usize,
+    /// Number of batches moved to quarantine (permanent error or exhausted retry budget)
+    pub quarantined: usize,
 }
 
 /// Statistics from a cleanup operation
@@ -72,6 +153,8 @@ mod tests {
             timestamp: Utc::now(),
             record_count: 3,
             records: vec![1, 2, 3],
+            retry_count: 0,
+            checksum: Some(42),
         };
 
         let json = serde_json::to_string(&envelope).unwrap();
@@ -80,6 +163,44 @@ mod tests {
         assert_eq!(envelope.table_name, deserialized.table_name);
         assert_eq!(envelope.record_count, deserialized.record_count);
         assert_eq!(envelope.records, deserialized.records);
+        assert_eq!(envelope.checksum, deserialized.checksum);
+    }
+
+    #[test]
+    fn test_batch_envelope_missing_retry_count_defaults_to_zero() {
+        // Envelopes persisted before `retry_count` existed won't have the field
+        let json = r#"{"table_name":"mlop_metrics","timestamp":"2024-01-01T00:00:00Z","record_count":1,"records":[1]}"#;
+        let envelope: BatchEnvelope<i32> = serde_json::from_str(json).unwrap();
+        assert_eq!(envelope.retry_count, 0);
+    }
+
+    #[test]
+    fn test_batch_envelope_missing_checksum_defaults_to_none() {
+        // Envelopes persisted before `checksum` existed won't have the field, and
+        // must not be treated as corrupt just because it's absent.
+        let json = r#"{"table_name":"mlop_metrics","timestamp":"2024-01-01T00:00:00Z","record_count":1,"records":[1],"retry_count":0}"#;
+        let envelope: BatchEnvelope<i32> = serde_json::from_str(json).unwrap();
+        assert_eq!(envelope.checksum, None);
+    }
+
+    #[test]
+    fn test_batch_manifest_serialization() {
+        let manifest = BatchManifest {
+            created_at: Utc::now(),
+            size_bytes: 1024,
+            row_count: 3,
+            source_table: "mlop_metrics".to_string(),
+            retry_count: 1,
+        };
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        let deserialized: BatchManifest = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(manifest.created_at, deserialized.created_at);
+        assert_eq!(manifest.size_bytes, deserialized.size_bytes);
+        assert_eq!(manifest.row_count, deserialized.row_count);
+        assert_eq!(manifest.source_table, deserialized.source_table);
+        assert_eq!(manifest.retry_count, deserialized.retry_count);
     }
 
     #[test]
@@ -88,5 +209,6 @@ mod tests {
         assert_eq!(stats.batches_persisted_total, 0);
         assert_eq!(stats.batches_replayed_total, 0);
         assert_eq!(stats.batches_pending, 0);
+        assert_eq!(stats.batches_quarantined_total, 0);
     }
 }
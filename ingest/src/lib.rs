@@ -4,6 +4,7 @@ pub mod config;
 pub mod db;
 pub mod error;
 pub mod models;
+pub mod pool;
 pub mod processors;
 pub mod routes;
 pub mod traits;
@@ -4,6 +4,7 @@ mod db;
 mod dlq;
 mod error;
 mod models;
+mod pool;
 mod processors;
 mod routes;
 mod traits;
@@ -28,7 +29,7 @@ use tokio::sync::mpsc;
 use crate::db::Database;
 use crate::models::metrics::MetricRow;
 use crate::processors::background::start_background_processor;
-use crate::routes::{files, health, ingest, AppState};
+use crate::routes::{bulk, files, health, ingest, query, AppState};
 
 // Define command-line arguments
 #[derive(Parser, Debug)]
@@ -111,6 +112,20 @@ async fn main() {
     // Wrap database connection in an Arc for shared access
     let db = Arc::new(db);
 
+    // Watch the Postgres pool in the background so a dead connection surfaces in logs
+    // instead of only on the next request that needs it.
+    let pg_pool_supervisor = Arc::new(pool::PoolSupervisor::spawn(
+        "postgres",
+        pool::PoolSupervisorConfig::default(),
+        {
+            let db = db.clone();
+            move || {
+                let db = db.clone();
+                async move { db.ping().await.map_err(|e| e.to_string()) }
+            }
+        },
+    ));
+
     // Create MPSC channels for different data types to be processed in the background
     let (metrics_record_sender, metrics_record_receiver) = mpsc::channel::<MetricRow>(1_000);
     let (log_record_sender, log_record_receiver) = mpsc::channel::<LogRow>(1_000);
@@ -123,6 +138,25 @@ async fn main() {
         .with_user(config.clickhouse_user.clone())
         .with_password(config.clickhouse_password.clone());
 
+    // Watch the ClickHouse client in the background for the same reason.
+    let clickhouse_pool_supervisor = Arc::new(pool::PoolSupervisor::spawn(
+        "clickhouse",
+        pool::PoolSupervisorConfig::default(),
+        {
+            let clickhouse_client = clickhouse_client.clone();
+            move || {
+                let clickhouse_client = clickhouse_client.clone();
+                async move {
+                    clickhouse_client
+                        .query("SELECT 1")
+                        .execute()
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+            }
+        },
+    ));
+
     // Wrap config in an Arc for shared access
     let config = Arc::new(config);
 
@@ -142,6 +176,13 @@ async fn main() {
         info!("DLQ directories initialized");
     }
 
+    // Backend every DLQ write/list/delete goes through, selected by `dlq_config.backend`.
+    // Built unconditionally (not just when the scheduler loops are spawned below) since
+    // the ingest paths in `routes::bulk` persist failed batches through it too; `None`
+    // here means no remote-spill `ObjectStore` is wired up yet, so `build_backend` falls
+    // back to the filesystem backend unless `DLQ_BACKEND=object_store` is paired with one.
+    let dlq_backend = dlq::backend::build_backend(&dlq_config, None);
+
     // Replay batches from previous pod lifetime on startup (in background)
     // This runs asynchronously to avoid blocking server startup if there are many batches
     if dlq_config.enabled && dlq_config.replay_on_startup {
@@ -156,12 +197,14 @@ async fn main() {
                 &replay_client,
                 &replay_config,
                 crate::config::METRICS_TABLE_NAME,
+                None,
             ).await {
                 Ok(stats) => info!(
                     table = crate::config::METRICS_TABLE_NAME,
                     replayed = stats.replayed,
                     failed_batches = stats.failed_batches,
                     failed_records = stats.failed_records,
+                    quarantined = stats.quarantined,
                     "Metrics DLQ startup replay completed"
                 ),
                 Err(e) => tracing::error!(error = %e, "Metrics DLQ startup replay failed"),
@@ -172,12 +215,14 @@ async fn main() {
                 &replay_client,
                 &replay_config,
                 crate::config::LOGS_TABLE_NAME,
+                None,
             ).await {
                 Ok(stats) => info!(
                     table = crate::config::LOGS_TABLE_NAME,
                     replayed = stats.replayed,
                     failed_batches = stats.failed_batches,
                     failed_records = stats.failed_records,
+                    quarantined = stats.quarantined,
                     "Logs DLQ startup replay completed"
                 ),
                 Err(e) => tracing::error!(error = %e, "Logs DLQ startup replay failed"),
@@ -188,12 +233,14 @@ async fn main() {
                 &replay_client,
                 &replay_config,
                 crate::config::DATA_TABLE_NAME,
+                None,
             ).await {
                 Ok(stats) => info!(
                     table = crate::config::DATA_TABLE_NAME,
                     replayed = stats.replayed,
                     failed_batches = stats.failed_batches,
                     failed_records = stats.failed_records,
+                    quarantined = stats.quarantined,
                     "Data DLQ startup replay completed"
                 ),
                 Err(e) => tracing::error!(error = %e, "Data DLQ startup replay failed"),
@@ -204,12 +251,14 @@ async fn main() {
                 &replay_client,
                 &replay_config,
                 crate::config::FILES_TABLE_NAME,
+                None,
             ).await {
                 Ok(stats) => info!(
                     table = crate::config::FILES_TABLE_NAME,
                     replayed = stats.replayed,
                     failed_batches = stats.failed_batches,
                     failed_records = stats.failed_records,
+                    quarantined = stats.quarantined,
                     "Files DLQ startup replay completed"
                 ),
                 Err(e) => tracing::error!(error = %e, "Files DLQ startup replay failed"),
@@ -255,98 +304,76 @@ async fn main() {
         dlq_config.clone(),
     ));
 
-    // Spawn DLQ background tasks
+    // Spawn the unified DLQ scheduler: one loop per table, each driving a prioritized
+    // quota-enforcement -> replay -> TTL-cleanup pass over that table's pending batches,
+    // replacing the previously-independent replay and cleanup timers (see
+    // `dlq::scheduler`).
     if dlq_config.enabled {
-        // Spawn cleanup task with panic recovery
-        let cleanup_config = dlq_config.clone();
-        tokio::spawn(async move {
-            let mut restart_count = 0u32;
-            let max_backoff_secs = 300; // 5 minutes
-
-            loop {
-                info!(restart_count = restart_count, "Starting DLQ cleanup task");
-
-                // Spawn cleanup loop in nested task to catch panics
-                let task_config = cleanup_config.clone();
-                let handle = tokio::spawn(async move {
-                    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(task_config.cleanup_interval_secs));
-                    loop {
-                        interval.tick().await;
-                        if let Err(e) = dlq::cleanup::cleanup_expired_batches(&task_config).await {
-                            tracing::error!(error = %e, "DLQ cleanup failed");
-                        }
-                        if let Err(e) = dlq::cleanup::enforce_disk_quota(&task_config).await {
-                            tracing::error!(error = %e, "DLQ quota enforcement failed");
-                        }
-                    }
-                });
-
-                // Wait for task to complete (panic or normal exit)
-                match handle.await {
-                    Ok(_) => {
-                        tracing::warn!("DLQ cleanup task exited normally (unexpected)");
-                    }
-                    Err(e) => {
-                        tracing::error!(error = %e, restart_count = restart_count, "DLQ cleanup task panicked, will restart");
-                    }
-                }
-
-                // Exponential backoff before restart (capped at max_backoff_secs)
-                restart_count += 1;
-                let backoff_secs = (2u64.pow(restart_count).min(max_backoff_secs as u64)) as u64;
-                tracing::warn!(backoff_secs = backoff_secs, "Waiting before restarting DLQ cleanup task");
-                tokio::time::sleep(tokio::time::Duration::from_secs(backoff_secs)).await;
-            }
-        });
-
-        info!("DLQ cleanup task spawned");
-
-        // Spawn replay tasks for continuous retry of failed batches
-        // Metrics replay loop
-        let metrics_client = clickhouse_client.clone();
-        let metrics_config = dlq_config.clone();
-        tokio::spawn(async move {
-            dlq::replay::start_replay_loop::<MetricRow, _, _>(
-                metrics_client,
-                metrics_config,
+        let metrics_scheduler = Arc::new(dlq::scheduler::DlqScheduler::new(
+            dlq::scheduler::default_handlers::<MetricRow, _, _>(
+                clickhouse_client.clone(),
                 crate::config::METRICS_TABLE_NAME.to_string(),
-            ).await;
-        });
-
-        // Logs replay loop
-        let logs_client = clickhouse_client.clone();
-        let logs_config = dlq_config.clone();
-        tokio::spawn(async move {
-            dlq::replay::start_replay_loop::<LogRow, _, _>(
-                logs_client,
-                logs_config,
+                None,
+                dlq_backend.clone(),
+            ),
+            dlq_backend.clone(),
+        ));
+        let metrics_config = dlq_config.clone();
+        tokio::spawn(dlq::scheduler::start_scheduler_loop(
+            metrics_scheduler,
+            metrics_config,
+            crate::config::METRICS_TABLE_NAME.to_string(),
+        ));
+
+        let logs_scheduler = Arc::new(dlq::scheduler::DlqScheduler::new(
+            dlq::scheduler::default_handlers::<LogRow, _, _>(
+                clickhouse_client.clone(),
                 crate::config::LOGS_TABLE_NAME.to_string(),
-            ).await;
-        });
-
-        // Data replay loop
-        let data_client = clickhouse_client.clone();
-        let data_config = dlq_config.clone();
-        tokio::spawn(async move {
-            dlq::replay::start_replay_loop::<DataRow, _, _>(
-                data_client,
-                data_config,
+                None,
+                dlq_backend.clone(),
+            ),
+            dlq_backend.clone(),
+        ));
+        let logs_config = dlq_config.clone();
+        tokio::spawn(dlq::scheduler::start_scheduler_loop(
+            logs_scheduler,
+            logs_config,
+            crate::config::LOGS_TABLE_NAME.to_string(),
+        ));
+
+        let data_scheduler = Arc::new(dlq::scheduler::DlqScheduler::new(
+            dlq::scheduler::default_handlers::<DataRow, _, _>(
+                clickhouse_client.clone(),
                 crate::config::DATA_TABLE_NAME.to_string(),
-            ).await;
-        });
-
-        // Files replay loop
-        let files_client = clickhouse_client.clone();
-        let files_config = dlq_config.clone();
-        tokio::spawn(async move {
-            dlq::replay::start_replay_loop::<FilesRow, _, _>(
-                files_client,
-                files_config,
+                None,
+                dlq_backend.clone(),
+            ),
+            dlq_backend.clone(),
+        ));
+        let data_config = dlq_config.clone();
+        tokio::spawn(dlq::scheduler::start_scheduler_loop(
+            data_scheduler,
+            data_config,
+            crate::config::DATA_TABLE_NAME.to_string(),
+        ));
+
+        let files_scheduler = Arc::new(dlq::scheduler::DlqScheduler::new(
+            dlq::scheduler::default_handlers::<FilesRow, _, _>(
+                clickhouse_client.clone(),
                 crate::config::FILES_TABLE_NAME.to_string(),
-            ).await;
-        });
+                None,
+                dlq_backend.clone(),
+            ),
+            dlq_backend.clone(),
+        ));
+        let files_config = dlq_config.clone();
+        tokio::spawn(dlq::scheduler::start_scheduler_loop(
+            files_scheduler,
+            files_config,
+            crate::config::FILES_TABLE_NAME.to_string(),
+        ));
 
-        info!("DLQ replay loops spawned for all tables");
+        info!(backend = dlq_backend.name(), "DLQ scheduler loops spawned for all tables");
     }
 
     // Create the application state, wrapping shared resources in Arc
@@ -358,6 +385,7 @@ async fn main() {
         clickhouse_client,
         db: db.clone(),
         dlq_config: dlq_config.clone(),
+        dlq_backend: dlq_backend.clone(),
         config: config.clone(),
     });
 
@@ -367,6 +395,8 @@ async fn main() {
         .merge(ingest::router())
         .merge(step::router())
         .merge(files::router())
+        .merge(query::router())
+        .merge(bulk::router())
         .with_state(state); // Provide the application state to the routes
 
     // Define the server address (IPv6)
@@ -376,6 +406,39 @@ async fn main() {
     // Bind the TCP listener and start the Axum server
     let ipv6_listener = TcpListener::bind(ipv6).await.unwrap();
     axum::serve(ipv6_listener, app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
+
+    // Stop handing out further health-check work and await the pool supervisors'
+    // in-flight probes before the runtime itself winds down.
+    info!("Server shut down, draining connection pool supervisors");
+    pg_pool_supervisor.terminate().await;
+    clickhouse_pool_supervisor.terminate().await;
+}
+
+/// Resolves once the process receives Ctrl+C or (on Unix) SIGTERM, for use as
+/// `axum::serve`'s graceful-shutdown future.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
@@ -171,28 +171,87 @@ impl IntoRows<MetricEnrichment, MetricRow> for MetricInput {
     fn into_rows(self, enrichment: MetricEnrichment) -> Result<Vec<MetricRow>, AppError> {
         self.validate()?;
 
+        let policy = enrichment.non_finite_policy;
         Ok(self
             .data
             .into_iter()
-            .map(|(log_name, value)| MetricRow {
-                time: self.time,
-                step: self.step,
-                log_group: log_group_from_log_name(&log_name),
-                log_name,
-                value,
-                tenant_id: enrichment.tenant_id.clone(),
-                run_id: enrichment.run_id.clone(),
-                project_name: enrichment.project_name.clone(),
+            .filter_map(|(log_name, value)| {
+                apply_non_finite_policy(value, policy).map(|value| MetricRow {
+                    time: self.time,
+                    step: self.step,
+                    log_group: log_group_from_log_name(&log_name),
+                    log_name,
+                    value,
+                    tenant_id: enrichment.tenant_id.clone(),
+                    run_id: enrichment.run_id,
+                    project_name: enrichment.project_name.clone(),
+                })
             })
             .collect())
     }
 }
 
+/// Governs how non-finite metric values (`NaN`, `Infinity`, `-Infinity`) are handled
+/// once they reach row conversion. The deserializer has no access to runtime config, so
+/// this is resolved from the `X-NonFinite-Policy` header and applied afterwards in
+/// `IntoRows::into_rows` / `DatabaseRow::from`, rather than in `deserialize_metric_data`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Keep non-finite values as-is (current behavior). Default when the header is absent.
+    #[default]
+    Preserve,
+    /// Silently skip metrics whose value is non-finite.
+    Drop,
+    /// Skip non-finite metrics, the same way `null` values are already skipped today.
+    Null,
+    /// Map `Infinity` to `f64::MAX`, `-Infinity` to `f64::MIN`, and drop `NaN`.
+    Clamp,
+}
+
+impl NonFinitePolicy {
+    fn from_header(headers: &HeaderMap) -> Self {
+        match headers
+            .get("X-NonFinite-Policy")
+            .and_then(|h| h.to_str().ok())
+            .map(|v| v.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("drop") => Self::Drop,
+            Some("null") => Self::Null,
+            Some("clamp") => Self::Clamp,
+            _ => Self::Preserve,
+        }
+    }
+}
+
+/// Applies `policy` to a metric value, returning `None` when the metric should be
+/// dropped entirely. Finite values always pass through unchanged.
+fn apply_non_finite_policy(value: f64, policy: NonFinitePolicy) -> Option<f64> {
+    if value.is_finite() {
+        return Some(value);
+    }
+
+    match policy {
+        NonFinitePolicy::Preserve => Some(value),
+        NonFinitePolicy::Drop | NonFinitePolicy::Null => None,
+        NonFinitePolicy::Clamp => {
+            if value.is_nan() {
+                None
+            } else if value == f64::INFINITY {
+                Some(f64::MAX)
+            } else {
+                Some(f64::MIN)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MetricEnrichment {
     pub tenant_id: String,
     pub run_id: u64,
     pub project_name: String,
+    pub non_finite_policy: NonFinitePolicy,
 }
 
 impl EnrichmentData for MetricEnrichment {
@@ -214,6 +273,7 @@ impl EnrichmentData for MetricEnrichment {
             tenant_id,
             run_id,
             project_name,
+            non_finite_policy: NonFinitePolicy::from_header(headers),
         })
     }
 }
@@ -243,13 +303,19 @@ impl DatabaseRow<MetricInput, MetricEnrichment> for MetricRow {
     fn from(input: MetricInput, enrichment: MetricEnrichment) -> Result<Self, AppError> {
         input.validate()?;
 
-        // Take the first metric or return an error if empty
-        let (log_name, value) = input.data.into_iter().next().ok_or_else(|| {
-            AppError::new(
-                ErrorCode::InvalidMetricFormat,
-                "'data' field cannot be empty".to_string(),
-            )
-        })?;
+        let policy = enrichment.non_finite_policy;
+
+        // Take the first metric surviving the non-finite policy, or error if none remain
+        let (log_name, value) = input
+            .data
+            .into_iter()
+            .find_map(|(name, value)| apply_non_finite_policy(value, policy).map(|v| (name, v)))
+            .ok_or_else(|| {
+                AppError::new(
+                    ErrorCode::InvalidMetricFormat,
+                    "'data' field cannot be empty".to_string(),
+                )
+            })?;
 
         Ok(Self {
             time: input.time,
@@ -366,6 +432,7 @@ mod tests {
             tenant_id: "test-tenant".to_string(),
             run_id: 1,
             project_name: "test-project".to_string(),
+            non_finite_policy: NonFinitePolicy::Preserve,
         };
         let rows = metric.into_rows(enrichment).unwrap();
         // Both finite and non-finite values are preserved
@@ -384,6 +451,7 @@ mod tests {
             tenant_id: "test-tenant".to_string(),
             run_id: 1,
             project_name: "test-project".to_string(),
+            non_finite_policy: NonFinitePolicy::Preserve,
         };
         let rows = metric.into_rows(enrichment).unwrap();
         // Both NaN and finite values are preserved
@@ -402,9 +470,103 @@ mod tests {
             tenant_id: "test-tenant".to_string(),
             run_id: 1,
             project_name: "test-project".to_string(),
+            non_finite_policy: NonFinitePolicy::Preserve,
         };
         let rows = metric.into_rows(enrichment).unwrap();
         // All non-finite values are preserved (stored in ClickHouse Float64)
         assert_eq!(rows.len(), 3);
     }
+
+    fn enrichment_with_policy(policy: NonFinitePolicy) -> MetricEnrichment {
+        MetricEnrichment {
+            tenant_id: "test-tenant".to_string(),
+            run_id: 1,
+            project_name: "test-project".to_string(),
+            non_finite_policy: policy,
+        }
+    }
+
+    #[test]
+    fn test_non_finite_policy_from_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-NonFinite-Policy", "drop".parse().unwrap());
+        assert_eq!(NonFinitePolicy::from_header(&headers), NonFinitePolicy::Drop);
+
+        headers.insert("X-NonFinite-Policy", "NULL".parse().unwrap());
+        assert_eq!(NonFinitePolicy::from_header(&headers), NonFinitePolicy::Null);
+
+        headers.insert("X-NonFinite-Policy", "Clamp".parse().unwrap());
+        assert_eq!(NonFinitePolicy::from_header(&headers), NonFinitePolicy::Clamp);
+
+        headers.insert("X-NonFinite-Policy", "preserve".parse().unwrap());
+        assert_eq!(
+            NonFinitePolicy::from_header(&headers),
+            NonFinitePolicy::Preserve
+        );
+    }
+
+    #[test]
+    fn test_non_finite_policy_defaults_to_preserve_when_header_absent() {
+        let headers = HeaderMap::new();
+        assert_eq!(
+            NonFinitePolicy::from_header(&headers),
+            NonFinitePolicy::Preserve
+        );
+    }
+
+    #[test]
+    fn test_into_rows_drop_policy_skips_non_finite() {
+        let input = br#"{"time": 100, "step": 1, "data": {"loss": 0.5, "a": NaN, "b": Infinity, "c": -Infinity}}"#;
+        let metric = parse_metric_input(input);
+        let rows = metric
+            .into_rows(enrichment_with_policy(NonFinitePolicy::Drop))
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].log_name, "loss");
+    }
+
+    #[test]
+    fn test_into_rows_null_policy_skips_non_finite() {
+        let input = br#"{"time": 100, "step": 1, "data": {"loss": 0.5, "a": NaN}}"#;
+        let metric = parse_metric_input(input);
+        let rows = metric
+            .into_rows(enrichment_with_policy(NonFinitePolicy::Null))
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].log_name, "loss");
+    }
+
+    #[test]
+    fn test_into_rows_clamp_policy_maps_infinities_and_drops_nan() {
+        let input = br#"{"time": 100, "step": 1, "data": {"a": NaN, "b": Infinity, "c": -Infinity, "d": 1.5}}"#;
+        let metric = parse_metric_input(input);
+        let rows = metric
+            .into_rows(enrichment_with_policy(NonFinitePolicy::Clamp))
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|r| r.log_name != "a"));
+        let b_row = rows.iter().find(|r| r.log_name == "b").unwrap();
+        let c_row = rows.iter().find(|r| r.log_name == "c").unwrap();
+        let d_row = rows.iter().find(|r| r.log_name == "d").unwrap();
+        assert_eq!(b_row.value, f64::MAX);
+        assert_eq!(c_row.value, f64::MIN);
+        assert_eq!(d_row.value, 1.5);
+    }
+
+    #[test]
+    fn test_database_row_from_applies_drop_policy() {
+        let input = br#"{"time": 100, "step": 1, "data": {"a": NaN, "loss": 0.5}}"#;
+        let metric = parse_metric_input(input);
+        let row = MetricRow::from(metric, enrichment_with_policy(NonFinitePolicy::Drop)).unwrap();
+        assert_eq!(row.log_name, "loss");
+        assert_eq!(row.value, 0.5);
+    }
+
+    #[test]
+    fn test_database_row_from_drop_errors_when_nothing_survives() {
+        let input = br#"{"time": 100, "step": 1, "data": {"a": NaN}}"#;
+        let metric = parse_metric_input(input);
+        let result = MetricRow::from(metric, enrichment_with_policy(NonFinitePolicy::Drop));
+        assert!(result.is_err());
+    }
 }
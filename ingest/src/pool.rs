@@ -0,0 +1,240 @@
+//! Background health monitoring for long-lived connection pools (Postgres, ClickHouse).
+//!
+//! Neither `sqlx::PgPool` nor `clickhouse::Client` notice a backend that's gone away
+//! until the next query tries to use it, so a dead connection otherwise surfaces as a
+//! request-path failure instead of something ops can see coming. `PoolSupervisor` runs a
+//! periodic probe (`SELECT 1` for Postgres, a ping query for ClickHouse) against an
+//! already-constructed pool/client in the background and backs off exponentially between
+//! retries while the backend stays unreachable, so a flapping database doesn't turn into
+//! a tight reconnect loop.
+//!
+//! `terminate` is the orderly-shutdown path: it stops the probe loop and awaits its
+//! current iteration so nothing is left running once the caller proceeds. `abort` is the
+//! fire-and-forget counterpart for contexts that can't `.await` — most importantly a
+//! synchronous `Drop` impl. The bug this split exists to avoid: code that `spawn_blocking`s
+//! a task and immediately `.unwrap()`s the `JoinHandle` panics if that spawn happens while
+//! the executor is already shutting down. `abort` never spawns and never unwraps a result
+//! that could be an `Err` from a cancelled runtime, so it's safe to call unconditionally
+//! during teardown.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Tunables for a [`PoolSupervisor`]'s background probe loop.
+#[derive(Debug, Clone)]
+pub struct PoolSupervisorConfig {
+    /// Warm connections the caller's pool is expected to keep open. Informational only —
+    /// the supervisor doesn't construct or own the pool, just watches it, so this is
+    /// forwarded by the caller to the pool's own builder (e.g.
+    /// `PgPoolOptions::max_connections`).
+    pub pool_size: u32,
+    /// How often the probe runs while the backend is healthy.
+    pub health_check_interval: Duration,
+    /// Delay before the first retry after a probe failure.
+    pub initial_backoff: Duration,
+    /// Upper bound the exponential backoff is capped at.
+    pub max_backoff: Duration,
+}
+
+impl Default for PoolSupervisorConfig {
+    fn default() -> Self {
+        Self {
+            pool_size: 10,
+            health_check_interval: Duration::from_secs(30),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Watches a single pool/client's health in the background: after a failed probe it logs
+/// and waits with exponential backoff before probing again. It does not eject, rebuild or
+/// reconnect the pool/client. Constructed with [`PoolSupervisor::spawn`], which takes
+/// ownership of the probe closure; the pool/client it probes is expected to be captured
+/// by that closure (typically a cloned handle, since both `PgPool` and `clickhouse::Client`
+/// are cheap to clone).
+pub struct PoolSupervisor {
+    name: &'static str,
+    shutdown: Arc<AtomicBool>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl PoolSupervisor {
+    /// Spawns the background probe loop and returns a handle to it.
+    pub fn spawn<F, Fut>(name: &'static str, config: PoolSupervisorConfig, probe: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send,
+    {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let loop_shutdown = shutdown.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut backoff = config.initial_backoff;
+
+            while !loop_shutdown.load(Ordering::Acquire) {
+                tokio::time::sleep(config.health_check_interval).await;
+                if loop_shutdown.load(Ordering::Acquire) {
+                    break;
+                }
+
+                match probe().await {
+                    Ok(()) => {
+                        backoff = config.initial_backoff;
+                    }
+                    Err(error) => {
+                        warn!(
+                            pool = name,
+                            %error,
+                            backoff_secs = backoff.as_secs_f64(),
+                            "connection pool health probe failed, backing off before retry"
+                        );
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(config.max_backoff);
+                    }
+                }
+            }
+
+            info!(pool = name, "connection pool supervisor stopped");
+        });
+
+        Self {
+            name,
+            shutdown,
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+
+    /// Stops the probe loop and awaits its current iteration before returning. This is
+    /// the orderly-shutdown path: use it anywhere the caller can `.await`, such as the
+    /// server's graceful-shutdown sequence. Safe to call more than once.
+    pub async fn terminate(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        let handle = self.handle.lock().unwrap().take();
+        if let Some(handle) = handle {
+            if let Err(e) = handle.await {
+                if !e.is_cancelled() {
+                    warn!(pool = self.name, error = %e, "connection pool supervisor task panicked");
+                }
+            }
+        }
+    }
+
+    /// Stops the probe loop without awaiting it. Unlike [`terminate`](Self::terminate),
+    /// this never blocks and can't panic, so it's safe to call from a synchronous `Drop`
+    /// impl even while the async runtime underneath it is already winding down.
+    pub fn abort(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for PoolSupervisor {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    #[tokio::test]
+    async fn test_terminate_stops_the_probe_loop() {
+        let probe_count = Arc::new(AtomicU32::new(0));
+        let counted_probe = probe_count.clone();
+
+        let supervisor = PoolSupervisor::spawn(
+            "test",
+            PoolSupervisorConfig {
+                health_check_interval: Duration::from_millis(5),
+                ..Default::default()
+            },
+            move || {
+                let counted_probe = counted_probe.clone();
+                async move {
+                    counted_probe.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                }
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        supervisor.terminate().await;
+
+        let observed_after_terminate = probe_count.load(Ordering::Relaxed);
+        assert!(observed_after_terminate > 0);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(probe_count.load(Ordering::Relaxed), observed_after_terminate);
+    }
+
+    #[tokio::test]
+    async fn test_abort_does_not_panic_and_stops_the_loop() {
+        let probe_count = Arc::new(AtomicU32::new(0));
+        let counted_probe = probe_count.clone();
+
+        let supervisor = PoolSupervisor::spawn(
+            "test",
+            PoolSupervisorConfig {
+                health_check_interval: Duration::from_millis(5),
+                ..Default::default()
+            },
+            move || {
+                let counted_probe = counted_probe.clone();
+                async move {
+                    counted_probe.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                }
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        supervisor.abort();
+
+        let observed_after_abort = probe_count.load(Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(probe_count.load(Ordering::Relaxed), observed_after_abort);
+    }
+
+    #[tokio::test]
+    async fn test_probing_continues_after_a_failed_probe() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counted_probe = attempts.clone();
+
+        let supervisor = PoolSupervisor::spawn(
+            "test",
+            PoolSupervisorConfig {
+                health_check_interval: Duration::from_millis(5),
+                initial_backoff: Duration::from_millis(5),
+                max_backoff: Duration::from_millis(20),
+                ..Default::default()
+            },
+            move || {
+                let counted_probe = counted_probe.clone();
+                async move {
+                    let n = counted_probe.fetch_add(1, Ordering::Relaxed);
+                    // Fail exactly once so we can observe the loop backing off and
+                    // then probing again rather than stopping.
+                    if n == 1 {
+                        Err("simulated failure".to_string())
+                    } else {
+                        Ok(())
+                    }
+                }
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        supervisor.terminate().await;
+        assert!(attempts.load(Ordering::Relaxed) > 2);
+    }
+}
@@ -0,0 +1,177 @@
+//! Bulk mixed-type ingest endpoint.
+//!
+//! `POST /ingest/bulk` accepts an array of heterogeneous operations — each tagged with
+//! its kind (metric, log, or data) and its payload — in a single request, so chatty
+//! clients aren't forced into one HTTP round-trip per data point. Each item is routed
+//! through the same `IntoRows`/`DatabaseRow` conversion as its single-record endpoint,
+//! and a per-item result is returned so a partial failure doesn't reject the whole batch.
+
+use axum::{extract::State, http::HeaderMap, routing::post, Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::error::{missing_header_error, AppError};
+use crate::models::data::{DataEnrichment, DataInput, DataRow};
+use crate::models::log::{LogEnrichment, LogInput, LogRow};
+use crate::models::metrics::{MetricEnrichment, MetricInput, MetricRow};
+use crate::processors::stream::IntoRows;
+use crate::routes::AppState;
+use crate::traits::EnrichmentData;
+
+/// One item of a bulk ingest request, tagged by its kind.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum BulkItem {
+    Metric(MetricInput),
+    Log(LogInput),
+    Data(DataInput),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkIngestRequest {
+    pub items: Vec<BulkItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkItemResult {
+    pub index: usize,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkIngestResponse {
+    pub results: Vec<BulkItemResult>,
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/ingest/bulk", post(bulk_ingest))
+}
+
+async fn bulk_ingest(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<BulkIngestRequest>,
+) -> Result<Json<BulkIngestResponse>, AppError> {
+    let tenant_id = headers
+        .get("X-Tenant-Id")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| missing_header_error("X-Tenant-Id"))?
+        .to_string();
+
+    let mut results = Vec::with_capacity(request.items.len());
+
+    for (index, item) in request.items.into_iter().enumerate() {
+        let result = match ingest_one(&state, &tenant_id, &headers, item).await {
+            Ok(()) => BulkItemResult {
+                index,
+                success: true,
+                error: None,
+            },
+            Err(e) => {
+                warn!(index, error = %e, "Bulk ingest item failed");
+                BulkItemResult {
+                    index,
+                    success: false,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        results.push(result);
+    }
+
+    Ok(Json(BulkIngestResponse { results }))
+}
+
+/// Converts and enqueues a single bulk item, matching the behavior of its corresponding
+/// single-record endpoint: rows that fail to reach the background sender are spilled to
+/// the DLQ instead of being dropped.
+async fn ingest_one(
+    state: &Arc<AppState>,
+    tenant_id: &str,
+    headers: &HeaderMap,
+    item: BulkItem,
+) -> Result<(), AppError> {
+    match item {
+        BulkItem::Metric(input) => {
+            let enrichment = MetricEnrichment::from_headers(tenant_id.to_string(), headers)?;
+            let rows = input.into_rows(enrichment)?;
+            for row in rows {
+                send_or_dlq(
+                    state.metrics_record_sender.clone(),
+                    row,
+                    crate::config::METRICS_TABLE_NAME,
+                    &state.dlq_config,
+                    &state.dlq_backend,
+                )
+                .await?;
+            }
+        }
+        BulkItem::Log(input) => {
+            let enrichment = LogEnrichment::from_headers(tenant_id.to_string(), headers)?;
+            let rows = input.into_rows(enrichment)?;
+            for row in rows {
+                send_or_dlq(
+                    state.log_record_sender.clone(),
+                    row,
+                    crate::config::LOGS_TABLE_NAME,
+                    &state.dlq_config,
+                    &state.dlq_backend,
+                )
+                .await?;
+            }
+        }
+        BulkItem::Data(input) => {
+            let enrichment = DataEnrichment::from_headers(tenant_id.to_string(), headers)?;
+            let rows = input.into_rows(enrichment)?;
+            for row in rows {
+                send_or_dlq(
+                    state.data_record_sender.clone(),
+                    row,
+                    crate::config::DATA_TABLE_NAME,
+                    &state.dlq_config,
+                    &state.dlq_backend,
+                )
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn send_or_dlq<T>(
+    sender: tokio::sync::mpsc::Sender<T>,
+    row: T,
+    table_name: &'static str,
+    dlq_config: &Arc<crate::dlq::DlqConfig>,
+    dlq_backend: &Arc<dyn crate::dlq::backend::DlqBackend>,
+) -> Result<(), AppError>
+where
+    T: serde::Serialize + Clone,
+{
+    if let Err(e) = sender.try_send(row) {
+        let row = e.into_inner();
+        if dlq_config.enabled {
+            if let Err(dlq_err) =
+                crate::dlq::persist_batch(&[row], table_name.to_string(), dlq_config, dlq_backend, None)
+                    .await
+            {
+                return Err(AppError::new(
+                    crate::error::ErrorCode::InternalError,
+                    format!("failed to enqueue or persist row to DLQ: {}", dlq_err),
+                ));
+            }
+        } else {
+            return Err(AppError::new(
+                crate::error::ErrorCode::InternalError,
+                "background processor channel is full and DLQ is disabled".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -134,11 +134,18 @@ async fn dlq_health(State(state): State<Arc<AppState>>) -> Json<dlq::types::DlqH
             // Reading BatchEnvelope.record_count from each file would be more accurate
             // but requires deserializing potentially thousands of files.
             // Actual record counts are available when replaying batches.
-            for batch_path in batches {
-                if let Ok(metadata) = tokio::fs::metadata(&batch_path).await {
+            for batch_path in &batches {
+                if let Ok(metadata) = tokio::fs::metadata(batch_path).await {
                     stats.records_pending += (metadata.len() / 1024) as u64;
                 }
             }
+
+            let remote = batches
+                .iter()
+                .filter(|p| dlq::storage::is_remote_stub(p))
+                .count() as u64;
+            stats.batches_pending_remote += remote;
+            stats.batches_pending_local += batches.len() as u64 - remote;
         }
     }
 
@@ -147,6 +154,9 @@ async fn dlq_health(State(state): State<Arc<AppState>>) -> Json<dlq::types::DlqH
         stats.disk_usage_mb = disk_bytes / 1024 / 1024;
     }
 
+    stats.batches_quarantined_total = dlq::storage::batches_quarantined_total();
+    stats.batches_archived_total = dlq::archive::batches_archived_total();
+
     // Find oldest batch (this would require parsing filenames or reading metadata)
     // For now, we'll skip this calculation to keep the endpoint fast
 
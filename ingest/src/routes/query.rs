@@ -0,0 +1,247 @@
+//! Read-side streaming query endpoint.
+//!
+//! Exposes `GET /query/metrics`, which streams ClickHouse rows back to the client in
+//! bounded chunks instead of buffering the full result set in memory. Two modes are
+//! supported: `snapshot` (query the existing rows once and terminate) and `subscribe`
+//! (emit the snapshot, then keep the connection open and push rows matching the
+//! selectors as they are passed to [`publish_metric_row`]). No ingest handler calls
+//! `publish_metric_row`, so rows ingested through the ingest routes do not reach a
+//! `subscribe` stream.
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::error;
+
+use crate::error::{missing_header_error, AppError};
+use crate::models::metrics::MetricRow;
+use crate::routes::AppState;
+
+/// Target size, in bytes, for a single streamed chunk before it is flushed to the client.
+/// Mirrors the chunked-flush threshold used elsewhere for large formatted payloads.
+const CHUNK_SIZE_TARGET_BYTES: usize = 256 * 1024;
+
+/// How long a `subscribe` stream waits for the next live row before closing the connection.
+const SUBSCRIBE_IDLE_TIMEOUT_SECS: u64 = 300;
+
+/// Process-wide fan-out of metric rows, used to drive `subscribe` queries. Rows enter it
+/// only through [`publish_metric_row`], which the metrics ingest handlers do not call;
+/// live `/query/metrics?mode=subscribe` streams subscribe to the same channel and filter
+/// by their selectors.
+static METRIC_UPDATES: OnceLock<broadcast::Sender<MetricRow>> = OnceLock::new();
+
+fn metric_updates_channel() -> &'static broadcast::Sender<MetricRow> {
+    METRIC_UPDATES.get_or_init(|| broadcast::channel(1024).0)
+}
+
+/// Publishes a freshly-ingested metric row to any open `subscribe` query streams.
+/// A send with no active receivers is expected (no live subscribers) and is ignored.
+pub fn publish_metric_row(row: MetricRow) {
+    let _ = metric_updates_channel().send(row);
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamMode {
+    #[default]
+    Snapshot,
+    Subscribe,
+}
+
+/// Query-string selectors for `GET /query/metrics`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct MetricQuerySelectors {
+    pub project_name: Option<String>,
+    pub run_id: Option<u64>,
+    pub metric_name: Option<String>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    #[serde(default)]
+    pub mode: StreamMode,
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/query/metrics", get(query_metrics))
+}
+
+async fn query_metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(selectors): Query<MetricQuerySelectors>,
+) -> Result<Response, AppError> {
+    let tenant_id = headers
+        .get("X-Tenant-Id")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| missing_header_error("X-Tenant-Id"))?
+        .to_string();
+
+    let (tx, rx) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(8);
+
+    let client = state.clickhouse_client.clone();
+    tokio::spawn(async move {
+        if let Err(e) = stream_snapshot(&client, &tenant_id, &selectors, &tx).await {
+            error!(error = %e, "Failed to stream metrics snapshot");
+            return;
+        }
+
+        if selectors.mode == StreamMode::Subscribe {
+            stream_live(&tenant_id, &selectors, &tx).await;
+        }
+    });
+
+    let body = Body::from_stream(ReceiverStream::new(rx));
+    Ok((StatusCode::OK, body).into_response())
+}
+
+/// Queries existing rows matching the selectors and flushes them to `tx` in chunks of
+/// roughly `CHUNK_SIZE_TARGET_BYTES`, rather than buffering the whole result set.
+async fn stream_snapshot(
+    client: &clickhouse::Client,
+    tenant_id: &str,
+    selectors: &MetricQuerySelectors,
+    tx: &mpsc::Sender<Result<Vec<u8>, std::io::Error>>,
+) -> Result<(), clickhouse::error::Error> {
+    let mut query = client
+        .query(&build_snapshot_sql(selectors))
+        .bind(tenant_id);
+
+    if let Some(project_name) = &selectors.project_name {
+        query = query.bind(project_name);
+    }
+    if let Some(run_id) = selectors.run_id {
+        query = query.bind(run_id);
+    }
+    if let Some(metric_name) = &selectors.metric_name {
+        query = query.bind(metric_name);
+    }
+    if let Some(start_time) = selectors.start_time {
+        query = query.bind(start_time);
+    }
+    if let Some(end_time) = selectors.end_time {
+        query = query.bind(end_time);
+    }
+
+    let mut cursor = query.fetch::<MetricRow>()?;
+    let mut buffer = Vec::with_capacity(CHUNK_SIZE_TARGET_BYTES + 1024);
+
+    while let Some(row) = cursor.next().await? {
+        append_row(&mut buffer, &row);
+
+        if buffer.len() >= CHUNK_SIZE_TARGET_BYTES {
+            let chunk = std::mem::replace(&mut buffer, Vec::with_capacity(CHUNK_SIZE_TARGET_BYTES + 1024));
+            if tx.send(Ok(chunk)).await.is_err() {
+                return Ok(()); // client disconnected
+            }
+        }
+    }
+
+    if !buffer.is_empty() {
+        let _ = tx.send(Ok(buffer)).await;
+    }
+
+    Ok(())
+}
+
+/// Builds the parameterized snapshot SQL for the given selectors. Time range and
+/// metric/project/run filters are appended as additional `?` placeholders, bound in the
+/// same order by the caller.
+fn build_snapshot_sql(selectors: &MetricQuerySelectors) -> String {
+    let mut sql = format!(
+        "SELECT time, step, logGroup, logName, value, tenantId, runId, projectName FROM {} WHERE tenantId = ?",
+        crate::config::METRICS_TABLE_NAME
+    );
+
+    if selectors.project_name.is_some() {
+        sql.push_str(" AND projectName = ?");
+    }
+    if selectors.run_id.is_some() {
+        sql.push_str(" AND runId = ?");
+    }
+    if selectors.metric_name.is_some() {
+        sql.push_str(" AND logName = ?");
+    }
+    if selectors.start_time.is_some() {
+        sql.push_str(" AND time >= ?");
+    }
+    if selectors.end_time.is_some() {
+        sql.push_str(" AND time <= ?");
+    }
+
+    sql.push_str(" ORDER BY time ASC");
+    sql
+}
+
+/// Keeps the connection open after the snapshot and pushes newly-ingested rows matching
+/// the selectors, until the idle timeout elapses or the client disconnects.
+async fn stream_live(
+    tenant_id: &str,
+    selectors: &MetricQuerySelectors,
+    tx: &mpsc::Sender<Result<Vec<u8>, std::io::Error>>,
+) {
+    let mut updates = metric_updates_channel().subscribe();
+
+    loop {
+        let next = tokio::time::timeout(
+            Duration::from_secs(SUBSCRIBE_IDLE_TIMEOUT_SECS),
+            updates.recv(),
+        )
+        .await;
+
+        match next {
+            Ok(Ok(row)) => {
+                if !row_matches_selectors(&row, tenant_id, selectors) {
+                    continue;
+                }
+
+                let mut buffer = Vec::with_capacity(256);
+                append_row(&mut buffer, &row);
+
+                if tx.send(Ok(buffer)).await.is_err() {
+                    return; // client disconnected
+                }
+            }
+            // A slow subscriber can miss rows during a burst; skip ahead rather than
+            // erroring the whole stream.
+            Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Err(broadcast::error::RecvError::Closed)) => return,
+            Err(_) => return, // idle timeout
+        }
+    }
+}
+
+fn row_matches_selectors(row: &MetricRow, tenant_id: &str, selectors: &MetricQuerySelectors) -> bool {
+    if row.tenant_id != tenant_id {
+        return false;
+    }
+    if let Some(project_name) = &selectors.project_name {
+        if &row.project_name != project_name {
+            return false;
+        }
+    }
+    if let Some(run_id) = selectors.run_id {
+        if row.run_id != run_id {
+            return false;
+        }
+    }
+    if let Some(metric_name) = &selectors.metric_name {
+        if &row.log_name != metric_name {
+            return false;
+        }
+    }
+    true
+}
+
+fn append_row(buffer: &mut Vec<u8>, row: &MetricRow) {
+    if serde_json::to_writer(&mut *buffer, row).is_ok() {
+        buffer.push(b'\n');
+    }
+}
@@ -6,14 +6,37 @@ pub fn log_group_from_log_name<S: AsRef<str>>(input: S) -> String {
     }
 }
 
-/// Sanitize JSON bytes by converting non-finite float literals (`NaN`, `Infinity`,
-/// `-Infinity`) into quoted JSON strings (`"NaN"`, `"Infinity"`, `"-Infinity"`).
+/// Bare-literal substitutions applied outside of JSON strings, checked in this order.
+/// `-Infinity` must come before `Infinity` since it would otherwise match as its suffix.
+const LITERAL_SUBSTITUTIONS: &[(&[u8], &[u8])] = &[
+    (b"-Infinity", b"\"-Infinity\""),
+    (b"Infinity", b"\"Infinity\""),
+    (b"NaN", b"\"NaN\""),
+    (b"None", b"null"),
+    (b"True", b"true"),
+    (b"False", b"false"),
+];
+
+/// Whether a byte can appear inside a bare identifier/literal token (letters, digits,
+/// underscore). Used to check that a matched literal isn't actually a substring of a
+/// longer token, e.g. the `NaN` in `NaN_count` or the `Infinity` in `Infinityish`.
+fn is_identifier_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Sanitize JSON bytes by converting bare non-JSON literals produced by Python's
+/// `json.dumps`/`repr` output — `NaN`, `Infinity`, `-Infinity`, `None`, `True`, `False` —
+/// into valid JSON (`"NaN"`, `"Infinity"`, `"-Infinity"`, `null`, `true`, `false`).
 /// Only converts occurrences outside of JSON string values.
 ///
+/// Matches are boundary-aware: a literal is only substituted when both the byte before
+/// and the byte after the match (or start/end of input) are non-identifier bytes, so a
+/// future token like `Infinityish` or `NaN_count` is left untouched.
+///
 /// These bare literals are produced by Python's `json.dumps` with `allow_nan=True`
-/// (the default) but are not valid JSON. By converting them to strings, downstream
-/// parsers (e.g., simd-json) can tokenize the input, and a custom serde deserializer
-/// can map them back to `f64::NAN`, `f64::INFINITY`, and `f64::NEG_INFINITY`.
+/// (the default) but are not valid JSON. By converting them, downstream parsers (e.g.,
+/// simd-json) can tokenize the input, and a custom serde deserializer can map the
+/// quoted float literals back to `f64::NAN`, `f64::INFINITY`, and `f64::NEG_INFINITY`.
 pub fn sanitize_json_non_finite_floats(input: &[u8]) -> Vec<u8> {
     let mut output = Vec::with_capacity(input.len() + 16); // small extra for added quotes
     let len = input.len();
@@ -44,32 +67,39 @@ pub fn sanitize_json_non_finite_floats(input: &[u8]) -> Vec<u8> {
             continue;
         }
 
-        // Check for -Infinity (9 bytes) — must check before Infinity
-        if input[i] == b'-' && i + 9 <= len && &input[i..i + 9] == b"-Infinity" {
-            output.extend_from_slice(b"\"-Infinity\"");
-            i += 9;
+        if let Some((consumed, replacement)) = match_literal_at(input, i) {
+            output.extend_from_slice(replacement);
+            i += consumed;
             continue;
         }
 
-        // Check for Infinity (8 bytes)
-        if input[i] == b'I' && i + 8 <= len && &input[i..i + 8] == b"Infinity" {
-            output.extend_from_slice(b"\"Infinity\"");
-            i += 8;
-            continue;
-        }
+        output.push(input[i]);
+        i += 1;
+    }
+
+    output
+}
 
-        // Check for NaN (3 bytes)
-        if input[i] == b'N' && i + 3 <= len && &input[i..i + 3] == b"NaN" {
-            output.extend_from_slice(b"\"NaN\"");
-            i += 3;
+/// Attempts to match one of `LITERAL_SUBSTITUTIONS` at position `i`, honoring the
+/// identifier-boundary rule. Returns the number of input bytes consumed and the
+/// replacement bytes to emit in their place.
+fn match_literal_at(input: &[u8], i: usize) -> Option<(usize, &'static [u8])> {
+    let len = input.len();
+
+    for (literal, replacement) in LITERAL_SUBSTITUTIONS {
+        let end = i + literal.len();
+        if end > len || &input[i..end] != *literal {
             continue;
         }
 
-        output.push(input[i]);
-        i += 1;
+        let preceded_ok = i == 0 || !is_identifier_byte(input[i - 1]);
+        let followed_ok = end == len || !is_identifier_byte(input[end]);
+        if preceded_ok && followed_ok {
+            return Some((literal.len(), replacement));
+        }
     }
 
-    output
+    None
 }
 
 #[cfg(test)]
@@ -170,4 +200,70 @@ mod tests {
             r#"{"time": 123, "step": 1, "data": {"a": "NaN", "b": "Infinity"}}"#
         );
     }
+
+    #[test]
+    fn test_sanitize_none() {
+        let input = br#"{"data": {"label": None, "acc": 0.95}}"#;
+        let output = sanitize_json_non_finite_floats(input);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"data": {"label": null, "acc": 0.95}}"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_true_false() {
+        let input = br#"{"data": {"converged": True, "diverged": False}}"#;
+        let output = sanitize_json_non_finite_floats(input);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"data": {"converged": true, "diverged": false}}"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_python_literals_and_non_finite_mixed() {
+        let input = br#"{"a": NaN, "b": None, "c": True, "d": False, "e": -Infinity}"#;
+        let output = sanitize_json_non_finite_floats(input);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"a": "NaN", "b": null, "c": true, "d": false, "e": "-Infinity"}"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_preserves_identifier_suffixed_tokens() {
+        // Bare tokens that merely start with a literal's bytes must not be corrupted.
+        let input = br#"{"a": NaN_count, "b": Infinityish, "c": Nonetheless, "d": Truest, "e": Falsetto}"#;
+        let output = sanitize_json_non_finite_floats(input);
+        assert_eq!(output, input.to_vec());
+    }
+
+    #[test]
+    fn test_sanitize_preserves_identifier_prefixed_tokens() {
+        // A literal's bytes appearing as the suffix of a longer token must not match.
+        let input = br#"{"a": xNaN, "b": myInfinity, "c": isNone, "d": isTrue, "e": isFalse}"#;
+        let output = sanitize_json_non_finite_floats(input);
+        assert_eq!(output, input.to_vec());
+    }
+
+    #[test]
+    fn test_sanitize_python_literals_preserved_in_strings() {
+        let input = br#"{"name": "NoneType", "desc": "True or False", "data": {"x": None}}"#;
+        let output = sanitize_json_non_finite_floats(input);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"name": "NoneType", "desc": "True or False", "data": {"x": null}}"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_literal_at_array_boundaries() {
+        let input = br#"{"data": [None,True,False,NaN]}"#;
+        let output = sanitize_json_non_finite_floats(input);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"data": [null,true,false,"NaN"]}"#
+        );
+    }
 }
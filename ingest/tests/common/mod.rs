@@ -182,6 +182,8 @@ pub fn create_test_app_state(
     db: Arc<server_rs::db::Database>,
     clickhouse_client: clickhouse::Client,
     config: Arc<server_rs::config::Config>,
+    dlq_config: Arc<DlqConfig>,
+    dlq_backend: Arc<dyn server_rs::dlq::backend::DlqBackend>,
 ) -> (
     Arc<server_rs::routes::AppState>,
     mpsc::Receiver<MetricRow>,
@@ -194,17 +196,6 @@ pub fn create_test_app_state(
     let (data_sender, data_receiver) = mpsc::channel::<DataRow>(100);
     let (files_sender, files_receiver) = mpsc::channel::<FilesRow>(100);
 
-    // Create a disabled DLQ config for testing
-    let dlq_config = Arc::new(DlqConfig {
-        enabled: false,
-        base_path: PathBuf::from("/tmp/dlq-test"),
-        max_disk_mb: 100,
-        batch_ttl_hours: 24,
-        replay_on_startup: false,
-        replay_interval_secs: 60,
-        cleanup_interval_secs: 60,
-    });
-
     let app_state = Arc::new(server_rs::routes::AppState {
         metrics_record_sender: metrics_sender,
         log_record_sender: log_sender,
@@ -213,6 +204,7 @@ pub fn create_test_app_state(
         clickhouse_client,
         db,
         dlq_config,
+        dlq_backend,
         config,
     });
 
@@ -241,10 +233,45 @@ pub struct TestFixture {
     _data_receiver: mpsc::Receiver<DataRow>,
     #[allow(dead_code)]
     _files_receiver: mpsc::Receiver<FilesRow>,
+    // Background health-check loops for the pools above. Dropping these aborts them
+    // (see `PoolSupervisor::abort`), which is what keeps teardown from racing the test's
+    // own tokio runtime shutdown.
+    pg_pool_supervisor: server_rs::pool::PoolSupervisor,
+    clickhouse_pool_supervisor: server_rs::pool::PoolSupervisor,
 }
 
 impl TestFixture {
+    /// Builds a fixture with the DLQ disabled, for tests that don't exercise it.
     pub async fn new() -> Self {
+        Self::build(DlqConfig {
+            enabled: false,
+            base_path: PathBuf::from("/tmp/dlq-test"),
+            max_disk_mb: 100,
+            batch_ttl_hours: 24,
+            replay_on_startup: false,
+            replay_interval_secs: 60,
+            max_concurrent_replays: 4,
+            max_replay_attempts: 10,
+            max_disk_bytes: None,
+            reserved_disk_ratio: 0.0,
+            remote_spill_bucket: None,
+            remote_spill_high_water_ratio: 0.8,
+            direct_io: false,
+            backend: server_rs::dlq::backend::DlqBackendKind::Filesystem,
+            archive_mode: server_rs::dlq::archive::ArchiveMode::HardDelete,
+            archive_bucket: None,
+        })
+        .await
+    }
+
+    /// Builds a fixture with `dlq_config` enabled against a real testcontainers
+    /// ClickHouse/Postgres stack, for integration coverage of the write/cleanup/quota/
+    /// replay cycle (see `dlq_integration_tests.rs`).
+    pub async fn with_dlq(dlq_config: DlqConfig) -> Self {
+        Self::build(dlq_config).await
+    }
+
+    async fn build(dlq_config: DlqConfig) -> Self {
         // Install rustls crypto provider (safe to call multiple times)
         let _ = rustls::crypto::CryptoProvider::install_default(
             rustls::crypto::aws_lc_rs::default_provider()
@@ -279,9 +306,46 @@ impl TestFixture {
         };
         let config = Arc::new(config);
 
+        server_rs::dlq::init_directories(&dlq_config)
+            .await
+            .expect("Failed to initialize DLQ directories");
+        let dlq_backend = server_rs::dlq::backend::build_backend(&dlq_config, None);
+        let dlq_config = Arc::new(dlq_config);
+
+        // Watch both pools in the background, same as the real server does, so
+        // integration tests exercise the same supervised-shutdown path.
+        let pg_pool_supervisor = server_rs::pool::PoolSupervisor::spawn(
+            "postgres",
+            server_rs::pool::PoolSupervisorConfig::default(),
+            {
+                let db = db.clone();
+                move || {
+                    let db = db.clone();
+                    async move { db.ping().await.map_err(|e| e.to_string()) }
+                }
+            },
+        );
+        let clickhouse_pool_supervisor = server_rs::pool::PoolSupervisor::spawn(
+            "clickhouse",
+            server_rs::pool::PoolSupervisorConfig::default(),
+            {
+                let clickhouse_client = clickhouse_client.clone();
+                move || {
+                    let clickhouse_client = clickhouse_client.clone();
+                    async move {
+                        clickhouse_client
+                            .query("SELECT 1")
+                            .execute()
+                            .await
+                            .map_err(|e| e.to_string())
+                    }
+                }
+            },
+        );
+
         // Create test app state and get channel receivers
         let (app_state, metrics_receiver, log_receiver, data_receiver, files_receiver) =
-            create_test_app_state(db, clickhouse_client, config);
+            create_test_app_state(db, clickhouse_client, config, dlq_config, dlq_backend);
 
         // Get API key and tenant ID from containers
         let api_key = containers.api_key.clone();
@@ -296,6 +360,8 @@ impl TestFixture {
             _log_receiver: log_receiver,
             _data_receiver: data_receiver,
             _files_receiver: files_receiver,
+            pg_pool_supervisor,
+            clickhouse_pool_supervisor,
         }
     }
 
@@ -303,6 +369,18 @@ impl TestFixture {
         Arc::clone(&self.app_state)
     }
 
+    pub fn dlq_config(&self) -> Arc<DlqConfig> {
+        Arc::clone(&self.app_state.dlq_config)
+    }
+
+    pub fn clickhouse_client(&self) -> clickhouse::Client {
+        self.app_state.clickhouse_client.clone()
+    }
+
+    pub fn clickhouse_url(&self) -> &str {
+        &self.containers.clickhouse_url
+    }
+
     pub fn router(&self) -> axum::Router {
         use server_rs::routes::ingest;
         axum::Router::new()
@@ -310,3 +388,15 @@ impl TestFixture {
             .with_state(self.app_state())
     }
 }
+
+impl Drop for TestFixture {
+    fn drop(&mut self) {
+        // `Drop` can't `.await`, so this can't call `PoolSupervisor::terminate` directly
+        // -- and a test's tokio runtime may already be tearing down by the time this
+        // runs, which is exactly the situation that used to panic when a pool's cleanup
+        // `spawn_blocking`'d a task and unwrapped its `JoinHandle`. `abort` sidesteps
+        // both problems: it never spawns and never awaits.
+        self.pg_pool_supervisor.abort();
+        self.clickhouse_pool_supervisor.abort();
+    }
+}